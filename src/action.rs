@@ -0,0 +1,348 @@
+use crate::modes::{
+    Actions, Launcher, Layouts, Mode, Search, Searches, Sessions, Substitute, Templates, Visual,
+    WorkspaceName, Workspaces,
+};
+use crate::{input::TextInput, Confirmation, State};
+use zellij_tile::prelude::*;
+
+/// What `Esc` does in the tab list, from the `esc_behavior` option.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EscBehavior {
+    /// Clear a kept filter, or close room when there's none, like fzf does.
+    #[default]
+    ClearThenClose,
+    Close,
+    ClearFilter,
+    Nothing,
+}
+
+impl std::str::FromStr for EscBehavior {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<EscBehavior, ()> {
+        match value {
+            "clear_then_close" => Ok(EscBehavior::ClearThenClose),
+            "close" => Ok(EscBehavior::Close),
+            "clear_filter" => Ok(EscBehavior::ClearFilter),
+            "nothing" => Ok(EscBehavior::Nothing),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What room does as soon as it knows the tabs, from the `action` option, so a Zellij keybinding
+/// can open it straight into one operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LaunchAction {
+    /// Rename the active tab, then close.
+    RenameCurrent,
+    /// Close the active tab.
+    DeleteCurrent,
+    /// Open a new tab with this name.
+    NewNamed(String),
+}
+
+impl LaunchAction {
+    /// Whether room just does it, without showing anything.
+    pub fn is_silent(&self) -> bool {
+        !matches!(self, LaunchAction::RenameCurrent)
+    }
+}
+
+impl std::str::FromStr for LaunchAction {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<LaunchAction, ()> {
+        match value {
+            "rename_current" => Ok(LaunchAction::RenameCurrent),
+            "delete_current" => Ok(LaunchAction::DeleteCurrent),
+            _ => match value.strip_prefix("new_named:").map(str::trim) {
+                Some(name) if !name.is_empty() => Ok(LaunchAction::NewNamed(name.to_string())),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+/// Something room can do from the tab list. The keymap turns keys into actions, `bind_<key>`
+/// options and `action` pipe messages name them, and `State::dispatch` runs them all.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    SelectNext,
+    SelectPrevious,
+    PageDown,
+    PageUp,
+    SelectFirst,
+    SelectLast,
+    /// Switch to the selected tab (or open the highlighted session tab or group).
+    FocusSelected,
+    /// Switch straight to the tab that was active before the current one.
+    FocusPrevious,
+    /// Go back through the tabs switched to from room.
+    GoBack,
+    GoForward,
+    ToggleMark,
+    /// Close the marked tabs, or the selected one, after confirming.
+    DeleteSelected,
+    ClearFilter,
+    Search,
+    PickSearch,
+    Launch,
+    PickLayout,
+    NewTabHere,
+    /// Open a new tab with the `new_tab_layout` layout.
+    NewTab,
+    FloatingPane,
+    NewPane,
+    PickCommand,
+    CopyName,
+    OpenScratch,
+    PickTemplate,
+    CycleSort,
+    ReverseSort,
+    Export,
+    DumpLayout,
+    /// Save the selected tab's layout to a file.
+    DumpTabLayout,
+    PickSession,
+    PickWorkspace,
+    ToggleWorkspace,
+    EditTags,
+    /// Give the target tabs the next color label.
+    CycleLabel,
+    /// Write a note for the selected tab.
+    EditNote,
+    CleanupExited,
+    Renumber,
+    Substitute,
+    Visual,
+    Close,
+    /// Revert the last rename, tag change or mark.
+    Undo,
+    Redo,
+    /// Run the `custom_action_<key>` script bound to a key.
+    RunCustom(char),
+    ApplyPreset(u8),
+    /// Select the next tab whose name starts with the character.
+    JumpTo(char),
+}
+
+impl Action {
+    /// The action called `name` in `bind_<key>` options and pipe messages, for those that don't
+    /// take an argument.
+    pub fn parse(name: &str) -> Option<Action> {
+        let action = match name.trim() {
+            "select_next" => Action::SelectNext,
+            "select_previous" => Action::SelectPrevious,
+            "page_down" => Action::PageDown,
+            "page_up" => Action::PageUp,
+            "select_first" => Action::SelectFirst,
+            "select_last" => Action::SelectLast,
+            "focus_selected" => Action::FocusSelected,
+            "focus_previous" => Action::FocusPrevious,
+            "go_back" => Action::GoBack,
+            "go_forward" => Action::GoForward,
+            "toggle_mark" => Action::ToggleMark,
+            "delete_selected" => Action::DeleteSelected,
+            "clear_filter" => Action::ClearFilter,
+            "search" => Action::Search,
+            "pick_search" => Action::PickSearch,
+            "launch" => Action::Launch,
+            "pick_layout" => Action::PickLayout,
+            "new_tab_here" => Action::NewTabHere,
+            "new_tab" => Action::NewTab,
+            "floating_pane" => Action::FloatingPane,
+            "new_pane" => Action::NewPane,
+            "pick_command" => Action::PickCommand,
+            "copy_name" => Action::CopyName,
+            "open_scratch" => Action::OpenScratch,
+            "pick_template" => Action::PickTemplate,
+            "cycle_sort" => Action::CycleSort,
+            "reverse_sort" => Action::ReverseSort,
+            "export" => Action::Export,
+            "dump_layout" => Action::DumpLayout,
+            "dump_tab_layout" => Action::DumpTabLayout,
+            "pick_session" => Action::PickSession,
+            "pick_workspace" => Action::PickWorkspace,
+            "toggle_workspace" => Action::ToggleWorkspace,
+            "edit_tags" => Action::EditTags,
+            "cycle_label" => Action::CycleLabel,
+            "edit_note" => Action::EditNote,
+            "cleanup_exited" => Action::CleanupExited,
+            "renumber" => Action::Renumber,
+            "substitute" => Action::Substitute,
+            "visual" => Action::Visual,
+            "close" => Action::Close,
+            "undo" => Action::Undo,
+            "redo" => Action::Redo,
+            _ => return None,
+        };
+
+        Some(action)
+    }
+
+    /// The action of a key in `Mode::Normal`: its `bind_<key>` binding, or else its default. Keys
+    /// are matched with their modifiers, so `Ctrl + d` pages down while `d` jumps.
+    pub fn for_key(state: &State, key: &KeyWithModifier) -> Option<Action> {
+        if let Some((_, action)) = state.bindings.iter().find(|(bound, _)| bound == key) {
+            return Some(*action);
+        }
+
+        let ctrl = key.has_only_modifiers(&[KeyModifier::Ctrl]);
+        let alt = key.has_only_modifiers(&[KeyModifier::Alt]);
+        // Shift only tells uppercase letters apart, so it still counts as a plain key
+        let plain = key.has_no_modifiers() || key.has_only_modifiers(&[KeyModifier::Shift]);
+
+        let action = match key.bare_key {
+            BareKey::Char('d') if ctrl => Action::PageDown,
+            BareKey::Char('u') if ctrl => Action::PageUp,
+            BareKey::Char('r') if ctrl => Action::Redo,
+            BareKey::Char('l') if alt => Action::DumpTabLayout,
+            _ if !plain => return None,
+            BareKey::PageDown => Action::PageDown,
+            BareKey::PageUp => Action::PageUp,
+            BareKey::Home => Action::SelectFirst,
+            BareKey::End => Action::SelectLast,
+            BareKey::Char(c) if state.custom_actions.contains_key(&c) => Action::RunCustom(c),
+            BareKey::F(number) => Action::ApplyPreset(number),
+            BareKey::Esc => match state.esc_behavior {
+                EscBehavior::ClearThenClose if !state.filter.is_empty() => Action::ClearFilter,
+                EscBehavior::ClearThenClose => Action::Close,
+                EscBehavior::Close => Action::Close,
+                EscBehavior::ClearFilter => Action::ClearFilter,
+                EscBehavior::Nothing => return None,
+            },
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => Action::SelectNext,
+            BareKey::Up | BareKey::Char('k') => Action::SelectPrevious,
+            BareKey::Enter => Action::FocusSelected,
+            BareKey::Char('-') => Action::FocusPrevious,
+            BareKey::Char('[') => Action::GoBack,
+            BareKey::Char(']') => Action::GoForward,
+            BareKey::Char(' ') => Action::ToggleMark,
+            BareKey::Char('x') => Action::DeleteSelected,
+            BareKey::Backspace => Action::ClearFilter,
+            BareKey::Char('/') => Action::Search,
+            BareKey::Char('f') => Action::PickSearch,
+            BareKey::Char(':') => Action::Launch,
+            BareKey::Char('n') => Action::PickLayout,
+            BareKey::Char('N') => Action::NewTabHere,
+            BareKey::Char('T') => Action::NewTab,
+            BareKey::Char('F') => Action::FloatingPane,
+            BareKey::Char('!') => Action::PickCommand,
+            BareKey::Char('y') => Action::CopyName,
+            BareKey::Char('E') => Action::OpenScratch,
+            BareKey::Char('p') => Action::NewPane,
+            BareKey::Char('t') => Action::PickTemplate,
+            BareKey::Char('s') => Action::CycleSort,
+            BareKey::Char('S') => Action::ReverseSort,
+            BareKey::Char('e') => Action::Export,
+            BareKey::Char('L') => Action::DumpLayout,
+            BareKey::Char('o') => Action::PickSession,
+            BareKey::Char('w') => Action::PickWorkspace,
+            BareKey::Char('W') => Action::ToggleWorkspace,
+            BareKey::Char('@') => Action::EditTags,
+            BareKey::Char('C') => Action::CycleLabel,
+            BareKey::Char('a') => Action::EditNote,
+            BareKey::Char('c') => Action::CleanupExited,
+            BareKey::Char('#') => Action::Renumber,
+            BareKey::Char('%') => Action::Substitute,
+            BareKey::Char('V') => Action::Visual,
+            BareKey::Char('u') => Action::Undo,
+            BareKey::Char(c) if c.is_alphanumeric() => Action::JumpTo(c),
+            _ => return None,
+        };
+
+        Some(action)
+    }
+}
+
+impl State {
+    /// Runs an action, returning whether room needs to render again.
+    pub(crate) fn dispatch(&mut self, action: Action) -> bool {
+        self.log(format_args!("dispatch {action:?}"));
+
+        match action {
+            Action::SelectNext => self.select_down(),
+            Action::SelectPrevious => self.select_up(),
+            Action::PageDown => self.page(BareKey::PageDown),
+            Action::PageUp => self.page(BareKey::PageUp),
+            Action::SelectFirst => self.page(BareKey::Home),
+            Action::SelectLast => self.page(BareKey::End),
+            Action::FocusSelected if self.highlighted.is_some() => self.activate_highlighted(),
+            Action::FocusSelected => self.focus_selected_tab(),
+            Action::FocusPrevious => self.focus_previous_tab(),
+            Action::GoBack => self.walk_trail(false),
+            Action::GoForward => self.walk_trail(true),
+            Action::ToggleMark => {
+                self.toggle_mark();
+                self.select_down();
+            }
+            Action::DeleteSelected => {
+                let tab_ids: Vec<usize> = self.target_tabs().iter().map(|tab| tab.tab_id).collect();
+
+                if !tab_ids.is_empty() {
+                    self.confirm(Confirmation::CloseTabs(tab_ids));
+                }
+            }
+            Action::ClearFilter if self.filter.is_empty() => return false,
+            Action::ClearFilter => {
+                self.filter.clear();
+                self.reset_selection();
+            }
+            Action::Search => self.mode = Mode::Search(Search::new(&self.filter)),
+            Action::PickSearch => self.mode = Mode::Searches(Searches::default()),
+            Action::Launch => self.mode = Mode::Launcher(Launcher::default()),
+            Action::PickLayout => self.mode = Mode::Layouts(Layouts::default()),
+            Action::NewTabHere => self.new_tab_in_current_cwd(),
+            Action::NewTab => self.new_tab_from_layout(),
+            Action::FloatingPane => self.open_pane_in_selected_tab(true),
+            Action::NewPane => self.open_pane_in_selected_tab(false),
+            Action::PickCommand if self.selected.is_none() => return false,
+            Action::PickCommand => self.mode = Mode::Actions(Actions::default()),
+            Action::CopyName => {
+                let Some(name) = self.selected_tab().map(|tab| tab.name.clone()) else {
+                    return false;
+                };
+
+                copy_to_clipboard(&name);
+                self.status = Some(format!("copied {name}"));
+            }
+            Action::OpenScratch => self.open_scratch(),
+            Action::PickTemplate => self.mode = Mode::Templates(Templates::default()),
+            Action::CycleSort => self.sort_order = self.sort_order.next(),
+            Action::ReverseSort => self.sort_reverse = !self.sort_reverse,
+            Action::Export => self.export_tabs(),
+            Action::DumpLayout => self.dump_layout(),
+            Action::DumpTabLayout => self.dump_tab_layout(),
+            Action::PickSession => self.mode = Mode::Sessions(Sessions::default()),
+            Action::PickWorkspace => self.mode = Mode::Workspaces(Workspaces::default()),
+            Action::ToggleWorkspace if self.selected.is_none() => return false,
+            Action::ToggleWorkspace => self.mode = Mode::WorkspaceName(WorkspaceName::default()),
+            Action::EditTags => return self.start_tagging(),
+            Action::CycleLabel => return self.cycle_label(),
+            Action::EditNote => return self.start_note(),
+            Action::CleanupExited => self.cleanup_exited_tabs(),
+            Action::Renumber => self.renumber_tabs(),
+            Action::Substitute => {
+                self.mode = Mode::Substitute(Substitute {
+                    expression: TextInput::new("s/"),
+                });
+            }
+            Action::Visual => {
+                let Some(anchor) = self.selected_row() else {
+                    return false;
+                };
+
+                self.mode = Mode::Visual(Visual { anchor });
+            }
+            Action::Close => self.dismiss(),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::RunCustom(c) => self.run_custom_action(c),
+            Action::ApplyPreset(number) => return self.apply_preset(number),
+            Action::JumpTo(c) => return self.jump_to_initial(c),
+        }
+
+        true
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{
+    labels, labels::Label, storage, tags, trail, trail::Trail, workspaces, workspaces::Workspaces,
+    FOCUSED_FILE, NOTES_FILE, RECENT_FILE, SEARCHES_FILE, SELECTION_FILE,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Where the `export_data` and `import_data` pipes read and write when no path is given, relative
+/// to the folder Zellij was started in.
+pub const DEFAULT_PATH: &str = "room-data.json";
+
+/// Everything room keeps in its data dir, in one file that can be carried to another machine.
+/// Missing fields import as empty, so a file from an older room still loads.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Backup {
+    pub tags: BTreeMap<String, Vec<String>>,
+    pub labels: BTreeMap<String, Label>,
+    pub notes: BTreeMap<String, BTreeMap<String, String>>,
+    pub trails: BTreeMap<String, Trail>,
+    pub workspaces: BTreeMap<String, Workspaces>,
+    pub recent: BTreeMap<String, Vec<String>>,
+    pub focused: BTreeMap<String, BTreeMap<String, i64>>,
+    pub searches: BTreeMap<String, String>,
+    pub selection: BTreeMap<String, usize>,
+}
+
+impl Backup {
+    /// Reads every file from the data dir rather than room's own copies, which skip the saved
+    /// selection unless `restore_selection` is on.
+    pub fn collect() -> Backup {
+        Backup {
+            tags: storage::load(tags::FILE),
+            labels: storage::load(labels::FILE),
+            notes: storage::load(NOTES_FILE),
+            trails: storage::load(trail::FILE),
+            workspaces: storage::load(workspaces::FILE),
+            recent: storage::load(RECENT_FILE),
+            focused: storage::load(FOCUSED_FILE),
+            searches: storage::load(SEARCHES_FILE),
+            selection: storage::load(SELECTION_FILE),
+        }
+    }
+
+    /// Replaces the files in the data dir with the backup's.
+    pub fn restore(&self) {
+        storage::save(tags::FILE, &self.tags);
+        storage::save(labels::FILE, &self.labels);
+        storage::save(NOTES_FILE, &self.notes);
+        storage::save(trail::FILE, &self.trails);
+        storage::save(workspaces::FILE, &self.workspaces);
+        storage::save(RECENT_FILE, &self.recent);
+        storage::save(FOCUSED_FILE, &self.focused);
+        storage::save(SEARCHES_FILE, &self.searches);
+        storage::save(SELECTION_FILE, &self.selection);
+    }
+}
@@ -0,0 +1,73 @@
+/// A step of a `custom_action_<key>` script. Steps are separated by `;`, e.g.
+/// `"focus; run cargo test; rename {name} (testing)"`.
+#[derive(Debug)]
+pub enum Step {
+    /// Switch to the selected tab.
+    Focus,
+    /// Open a tiled terminal in the focused tab.
+    Pane,
+    /// Open a floating terminal in the focused tab.
+    Floating,
+    /// Run a command in a new pane of the focused tab.
+    Run(String),
+    /// Rename the selected tab; `{name}` stands for its current name.
+    Rename(String),
+}
+
+impl Step {
+    pub fn parse_script(script: &str) -> Option<Vec<Step>> {
+        script
+            .split(';')
+            .map(str::trim)
+            .filter(|step| !step.is_empty())
+            .map(|step| {
+                let (name, argument) = step.split_once(' ').unwrap_or((step, ""));
+                let argument = argument.trim().to_string();
+
+                match name {
+                    "focus" => Some(Step::Focus),
+                    "pane" => Some(Step::Pane),
+                    "floating" => Some(Step::Floating),
+                    "run" if !argument.is_empty() => Some(Step::Run(argument)),
+                    "rename" if !argument.is_empty() => Some(Step::Rename(argument)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the step leaves the tab list behind, so room should close.
+    pub fn leaves_room(&self) -> bool {
+        !matches!(self, Step::Rename(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Step;
+
+    #[test]
+    fn steps_are_separated_by_semicolons() {
+        let steps =
+            Step::parse_script(" focus;; run cargo test ;rename {name} (testing); ").unwrap();
+
+        assert!(matches!(
+            steps.as_slice(),
+            [Step::Focus, Step::Run(command), Step::Rename(format)]
+                if command == "cargo test" && format == "{name} (testing)"
+        ));
+    }
+
+    #[test]
+    fn run_and_rename_need_an_argument() {
+        assert!(Step::parse_script("rename").is_none());
+        assert!(Step::parse_script("focus; rename   ").is_none());
+        assert!(Step::parse_script("run").is_none());
+    }
+
+    #[test]
+    fn unknown_steps_fail_the_script() {
+        assert!(Step::parse_script("focus; split").is_none());
+        assert!(Step::parse_script("Focus").is_none());
+    }
+}
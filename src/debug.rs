@@ -0,0 +1,11 @@
+use crate::storage;
+use std::fmt::Arguments;
+
+pub const FILE: &str = "debug.log";
+
+/// Appends a timestamped line to `debug.log` in the plugin data dir, for attaching to bug reports.
+pub fn log(message: Arguments) {
+    let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f");
+
+    storage::append(FILE, &format!("{timestamp} {message}"));
+}
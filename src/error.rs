@@ -0,0 +1,36 @@
+use std::fmt;
+
+/// Everything that can go wrong in room. None of these are fatal: they're reported in the status
+/// line (and the debug log) and room keeps running.
+#[derive(Debug)]
+pub enum RoomError {
+    /// A configuration value that doesn't parse; its default is used instead.
+    Config { key: String, value: String },
+    /// Zellij refused or failed an action.
+    Zellij { action: String, message: String },
+    /// Reading or writing a file failed; `operation` says which.
+    Io {
+        operation: String,
+        path: String,
+        message: String,
+    },
+    /// Input typed into room that it can't make sense of.
+    Input(String),
+}
+
+impl fmt::Display for RoomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomError::Config { key, value } => {
+                write!(f, "invalid {key} {value:?}, using the default")
+            }
+            RoomError::Zellij { action, message } => write!(f, "failed to {action}: {message}"),
+            RoomError::Io {
+                operation,
+                path,
+                message,
+            } => write!(f, "failed to {operation} {path}: {message}"),
+            RoomError::Input(message) => write!(f, "{message}"),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+#[derive(Serialize)]
+pub struct TabSummary {
+    pub position: usize,
+    pub name: String,
+    pub active: bool,
+    pub panes: usize,
+    pub cwd: Option<String>,
+}
+
+/// How `e` prints the tabs, from `export_format`.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Format, ()> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn to_text(tabs: &[TabSummary]) -> String {
+    tabs.iter()
+        .map(|tab| {
+            format!(
+                "{}\t{}\t{} panes\t{}{}",
+                tab.position + 1,
+                tab.name,
+                tab.panes,
+                tab.cwd.as_deref().unwrap_or("-"),
+                if tab.active { "\t(active)" } else { "" }
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn to_json(tabs: &[TabSummary]) -> String {
+    serde_json::to_string_pretty(tabs).unwrap_or_default()
+}
+
+/// Prints the dump in a new command pane, which Zellij keeps open after `printf` exits so the text
+/// can be copied from it.
+pub fn open_in_pane(dump: String) {
+    let command_to_run = CommandToRun {
+        path: "printf".into(),
+        args: vec!["%s\n".to_string(), dump],
+        cwd: None,
+    };
+
+    open_command_pane(command_to_run, BTreeMap::new());
+}
+
+/// Cuts the `tab` node named `name` out of a session layout from `dump_session_layout`, wrapped in
+/// a `layout` node of its own so it can be used as a layout file. The session's `cwd` comes along,
+/// since the tab's panes may be relative to it.
+pub fn tab_layout(session_layout: &str, name: &str) -> Option<String> {
+    let quoted = format!("name={}", serde_json::to_string(name).ok()?);
+    let mut lines = session_layout.lines();
+    let first = lines.find(|line| {
+        let line = line.trim_start();
+
+        line.starts_with("tab ") && line.contains(&quoted)
+    })?;
+
+    let mut node = vec![first];
+    let mut depth = brace_depth(first);
+    while depth > 0 {
+        let line = lines.next()?;
+
+        depth += brace_depth(line);
+        node.push(line);
+    }
+
+    let indent = first.len() - first.trim_start().len();
+    let cwd = session_layout.lines().find(|line| {
+        line.len() - line.trim_start().len() == indent && line.trim_start().starts_with("cwd ")
+    });
+
+    let body: Vec<String> = cwd
+        .into_iter()
+        .chain(node)
+        .map(|line| format!("    {}", line.get(indent..).unwrap_or(line.trim_start())))
+        .collect();
+
+    Some(format!("layout {{\n{}\n}}\n", body.join("\n")))
+}
+
+/// How many braces a line of KDL opens, minus those it closes, skipping the ones in strings.
+fn brace_depth(line: &str) -> isize {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in line.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => depth -= 1,
+            _ => (),
+        }
+    }
+
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{brace_depth, tab_layout};
+
+    const SESSION: &str = r#"layout {
+    cwd "/home/user"
+    tab name="dev" focus=true {
+        pane split_direction="vertical" {
+            pane command="printf" {
+                args "}{" "\"{"
+            }
+            pane
+        }
+    }
+    tab name="dev server" {
+        pane cwd="src"
+    }
+    tab name="say \"hi\"" {
+        pane
+    }
+    new_tab_template {
+        pane
+    }
+}
+"#;
+
+    #[test]
+    fn braces_in_strings_are_skipped() {
+        assert_eq!(brace_depth(r#"pane command="printf" {"#), 1);
+        assert_eq!(brace_depth(r#"args "}{" "\"{""#), 0);
+        assert_eq!(brace_depth("}"), -1);
+    }
+
+    #[test]
+    fn nested_panes_come_along_with_their_tab() {
+        assert_eq!(
+            tab_layout(SESSION, "dev").as_deref(),
+            Some(
+                r#"layout {
+    cwd "/home/user"
+    tab name="dev" focus=true {
+        pane split_direction="vertical" {
+            pane command="printf" {
+                args "}{" "\"{"
+            }
+            pane
+        }
+    }
+}
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn names_match_whole_and_escaped() {
+        assert_eq!(
+            tab_layout(SESSION, "dev server").as_deref(),
+            Some("layout {\n    cwd \"/home/user\"\n    tab name=\"dev server\" {\n        pane cwd=\"src\"\n    }\n}\n")
+        );
+        assert_eq!(
+            tab_layout(SESSION, r#"say "hi""#).as_deref(),
+            Some("layout {\n    cwd \"/home/user\"\n    tab name=\"say \\\"hi\\\"\" {\n        pane\n    }\n}\n")
+        );
+    }
+
+    #[test]
+    fn missing_tab_is_none() {
+        assert_eq!(tab_layout(SESSION, "de"), None);
+        assert_eq!(tab_layout(SESSION, "logs"), None);
+    }
+}
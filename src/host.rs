@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::rc::Rc;
+use zellij_tile::prelude::*;
+
+/// The Zellij calls room makes to act on this session's tabs. `State` goes through `State::host`
+/// for them, so tests can record what room asked for instead of talking to Zellij.
+pub trait Host {
+    fn close_tab(&self, tab_id: usize);
+    /// Switches to the tab at a (zero based) position.
+    fn focus_tab(&self, position: usize);
+    fn rename_tab(&self, tab_id: usize, name: &str);
+    fn close_plugin(&self);
+    fn hide_plugin(&self);
+    fn show_plugin(&self);
+    /// Runs a command in a new pane of the focused tab.
+    fn open_command_pane(&self, command_to_run: CommandToRun);
+    fn post_to_worker(&self, worker_name: &str, message: &str, payload: &str);
+    /// Asks for a `Timer` event after `seconds`.
+    fn set_timeout(&self, seconds: f64);
+}
+
+/// Sends the calls to Zellij.
+pub struct Zellij;
+
+impl Host for Zellij {
+    fn close_tab(&self, tab_id: usize) {
+        close_tab_with_id(tab_id as u64);
+    }
+
+    fn focus_tab(&self, position: usize) {
+        switch_tab_to(position as u32 + 1);
+    }
+
+    fn rename_tab(&self, tab_id: usize, name: &str) {
+        rename_tab_with_id(tab_id as u64, name);
+    }
+
+    fn close_plugin(&self) {
+        close_self();
+    }
+
+    fn hide_plugin(&self) {
+        hide_self();
+    }
+
+    fn show_plugin(&self) {
+        show_self(true);
+    }
+
+    fn open_command_pane(&self, command_to_run: CommandToRun) {
+        open_command_pane(command_to_run, BTreeMap::new());
+    }
+
+    fn post_to_worker(&self, worker_name: &str, message: &str, payload: &str) {
+        post_message_to(PluginMessage::new_to_worker(worker_name, message, payload));
+    }
+
+    fn set_timeout(&self, seconds: f64) {
+        set_timeout(seconds);
+    }
+}
+
+/// The host `State` uses, which is Zellij unless a test swaps it.
+#[derive(Clone)]
+pub struct HostHandle(pub Rc<dyn Host>);
+
+impl Default for HostHandle {
+    fn default() -> HostHandle {
+        HostHandle(Rc::new(Zellij))
+    }
+}
+
+impl Deref for HostHandle {
+    type Target = dyn Host;
+
+    fn deref(&self) -> &(dyn Host + 'static) {
+        &*self.0
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+const PREFIX: &str = "icon_";
+
+/// Built in icons as `(command, nerd font glyph, ascii marker)`.
+const ICONS: [(&str, &str, &str); 16] = [
+    ("vim", "\u{e62b}", "v"),
+    ("nvim", "\u{e62b}", "v"),
+    ("hx", "\u{f044}", "e"),
+    ("docker", "\u{f308}", "d"),
+    ("ssh", "\u{f0318}", "@"),
+    ("cargo", "\u{e7a8}", "r"),
+    ("git", "\u{e702}", "g"),
+    ("lazygit", "\u{e702}", "g"),
+    ("node", "\u{e718}", "n"),
+    ("npm", "\u{e71e}", "n"),
+    ("python", "\u{e73c}", "p"),
+    ("python3", "\u{e73c}", "p"),
+    ("go", "\u{e626}", "g"),
+    ("htop", "\u{f2db}", "h"),
+    ("btop", "\u{f2db}", "h"),
+    ("man", "\u{f02d}", "?"),
+];
+
+/// Maps the command running in a tab to an icon. `nerd_fonts false` falls back to ascii markers
+/// and `icon_<command>` keys add or override icons.
+#[derive(Default)]
+pub struct Icons {
+    nerd_fonts: bool,
+    custom: BTreeMap<String, String>,
+}
+
+impl Icons {
+    pub fn from_configuration(configuration: &BTreeMap<String, String>, nerd_fonts: bool) -> Icons {
+        Icons {
+            nerd_fonts,
+            custom: configuration
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.strip_prefix(PREFIX)?.to_string(), value.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn icon(&self, command: &str) -> Option<&str> {
+        if let Some(icon) = self.custom.get(command) {
+            return Some(icon);
+        }
+
+        ICONS
+            .iter()
+            .find(|(candidate, _, _)| *candidate == command)
+            .map(|(_, glyph, marker)| if self.nerd_fonts { *glyph } else { *marker })
+    }
+
+    /// The icon of the tab's dominant command: the focused terminal's if it has one, otherwise
+    /// the one most of its terminals run.
+    pub fn for_panes(&self, panes: &[PaneInfo]) -> Option<&str> {
+        let terminals = || panes.iter().filter(|pane| !pane.is_plugin);
+
+        if let Some(icon) = terminals()
+            .filter(|pane| pane.is_focused)
+            .find_map(|pane| self.icon(pane_command(pane)?))
+        {
+            return Some(icon);
+        }
+
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for icon in terminals().filter_map(|pane| self.icon(pane_command(pane)?)) {
+            match counts.iter_mut().find(|(candidate, _)| *candidate == icon) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((icon, 1)),
+            }
+        }
+
+        // the first of the most common, so the first terminal wins ties
+        counts
+            .iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(icon, _)| *icon)
+    }
+}
+
+/// The program a pane runs: its command for command panes, otherwise the first word of its title,
+/// which shells usually set to the running program.
+pub fn pane_command(pane: &PaneInfo) -> Option<&str> {
+    let line = pane.terminal_command.as_deref().unwrap_or(&pane.title);
+    let program = line.split_whitespace().next()?;
+
+    program.rsplit('/').next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Icons;
+    use std::collections::BTreeMap;
+    use zellij_tile::prelude::*;
+
+    fn pane(title: &str, is_focused: bool, is_plugin: bool) -> PaneInfo {
+        PaneInfo {
+            title: title.to_string(),
+            is_focused,
+            is_plugin,
+            ..Default::default()
+        }
+    }
+
+    fn icons() -> Icons {
+        Icons::from_configuration(&BTreeMap::new(), false)
+    }
+
+    #[test]
+    fn focused_terminal_picks_the_icon() {
+        let panes = [
+            pane("cargo build", false, false),
+            pane("/usr/bin/nvim src/main.rs", true, false),
+            pane("cargo test", false, false),
+        ];
+
+        assert_eq!(icons().for_panes(&panes), Some("v"));
+    }
+
+    #[test]
+    fn without_a_focused_icon_the_most_common_or_first_wins() {
+        let panes = [
+            pane("zsh", true, false),
+            pane("htop", false, false),
+            pane("git log", false, false),
+            pane("git diff", false, false),
+        ];
+        assert_eq!(icons().for_panes(&panes), Some("g"));
+
+        let panes = [pane("htop", false, false), pane("nvim", false, false)];
+        assert_eq!(icons().for_panes(&panes), Some("h"));
+    }
+
+    #[test]
+    fn plugin_panes_are_ignored() {
+        let panes = [pane("nvim", true, true), pane("htop", false, false)];
+
+        assert_eq!(icons().for_panes(&panes), Some("h"));
+        assert_eq!(icons().for_panes(&[pane("nvim", true, true)]), None);
+    }
+}
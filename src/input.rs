@@ -0,0 +1,91 @@
+use owo_colors::OwoColorize;
+use zellij_tile::prelude::*;
+
+/// A line of text typed into a prompt, with a cursor that can move through it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextInput {
+    text: String,
+    /// Position of the cursor, in characters.
+    cursor: usize,
+}
+
+impl TextInput {
+    /// An input holding `text`, with the cursor after it.
+    pub fn new(text: &str) -> TextInput {
+        TextInput {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the whole text, e.g. with an entry recalled from a history or a preset.
+    pub fn set(&mut self, text: &str) {
+        *self = TextInput::new(text);
+    }
+
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(offset, _)| offset)
+    }
+
+    /// Edits the text with a key: characters are inserted at the cursor, `Backspace` and `Delete`
+    /// remove around it, `Ctrl + u` clears it all, and `Left`/`Right`, `Home`/`End` (or
+    /// `Ctrl + a`/`Ctrl + e`) move it.
+    /// Returns whether the key was used, so prompts can give other keys their own meaning.
+    pub fn handle_key(&mut self, key: &KeyWithModifier) -> bool {
+        let len = self.text.chars().count();
+        let ctrl = key.key_modifiers.contains(&KeyModifier::Ctrl);
+        let alt = key.key_modifiers.contains(&KeyModifier::Alt);
+
+        match key.bare_key {
+            BareKey::Char('a') if ctrl => self.cursor = 0,
+            BareKey::Char('e') if ctrl => self.cursor = len,
+            BareKey::Char('u') if ctrl => *self = TextInput::default(),
+            BareKey::Char(c) if !ctrl && !alt => {
+                let offset = self.byte_offset(self.cursor);
+
+                self.text.insert(offset, c);
+                self.cursor += 1;
+            }
+            BareKey::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+
+                let offset = self.byte_offset(self.cursor);
+                self.text.remove(offset);
+            }
+            BareKey::Delete if self.cursor < len => {
+                let offset = self.byte_offset(self.cursor);
+
+                self.text.remove(offset);
+            }
+            BareKey::Backspace | BareKey::Delete => (),
+            BareKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            BareKey::Right => self.cursor = (self.cursor + 1).min(len),
+            BareKey::Home => self.cursor = 0,
+            BareKey::End => self.cursor = len,
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// The text with the character under the cursor reversed, or `placeholder` while it's empty.
+    pub fn render(&self, placeholder: &str) -> String {
+        if self.text.is_empty() {
+            return placeholder.dimmed().italic().to_string();
+        }
+
+        let offset = self.byte_offset(self.cursor);
+        let (before, after) = self.text.split_at(offset);
+        let mut after = after.chars();
+        let under = after.next().unwrap_or(' ');
+
+        format!("{before}{}{}", under.reversed(), after.as_str())
+    }
+}
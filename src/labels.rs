@@ -0,0 +1,70 @@
+use owo_colors::{AnsiColors, OwoColorize};
+use serde::{Deserialize, Serialize};
+
+pub const FILE: &str = "labels.json";
+
+/// A color given to a tab to group it with related ones at a glance.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Label {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+/// The order `C` cycles through.
+const ALL: [Label; 6] = [
+    Label::Red,
+    Label::Green,
+    Label::Yellow,
+    Label::Blue,
+    Label::Magenta,
+    Label::Cyan,
+];
+
+impl Label {
+    /// The label after `label`, going back to none after the last one.
+    pub fn next(label: Option<Label>) -> Option<Label> {
+        match label {
+            None => Some(ALL[0]),
+            Some(label) => ALL
+                .iter()
+                .position(|other| *other == label)
+                .and_then(|index| ALL.get(index + 1))
+                .copied(),
+        }
+    }
+
+    /// Whether `name` (as in `%red` filter terms) names this label, ignoring case.
+    pub fn is_named(self, name: &str) -> bool {
+        let own = match self {
+            Label::Red => "red",
+            Label::Green => "green",
+            Label::Yellow => "yellow",
+            Label::Blue => "blue",
+            Label::Magenta => "magenta",
+            Label::Cyan => "cyan",
+        };
+
+        own.eq_ignore_ascii_case(name)
+    }
+
+    fn color(self) -> AnsiColors {
+        match self {
+            Label::Red => AnsiColors::Red,
+            Label::Green => AnsiColors::Green,
+            Label::Yellow => AnsiColors::Yellow,
+            Label::Blue => AnsiColors::Blue,
+            Label::Magenta => AnsiColors::Magenta,
+            Label::Cyan => AnsiColors::Cyan,
+        }
+    }
+
+    /// The colored chip rendered before a labelled tab.
+    pub fn chip(self) -> String {
+        "■".color(self.color()).to_string()
+    }
+}
@@ -1,209 +1,2919 @@
+mod action;
+mod backup;
+mod custom;
+mod debug;
+mod error;
+mod export;
+mod host;
+mod icons;
+mod input;
+mod labels;
+mod matcher;
+mod modes;
+mod pending;
+mod preview;
+mod renumber;
+mod select;
+mod sort;
+mod storage;
+mod substitute;
+mod tags;
+mod templates;
+#[cfg(test)]
+mod tests;
+mod theme;
+mod trail;
+mod undo;
+mod width;
+mod workspaces;
+
+use action::{Action, EscBehavior, LaunchAction};
+use custom::Step;
+use error::RoomError;
+use host::HostHandle;
+use icons::Icons;
+use input::TextInput;
+use matcher::{Candidate, MatchRequest, MatchResponse, MatchWorker};
+use modes::{Confirm, Mode, Normal, Note, Rename, Search, Sessions, Tag};
 use owo_colors::OwoColorize;
-use std::collections::BTreeMap;
+use pending::Pending;
+use preview::{PreviewRequest, PreviewResponse, PreviewWorker};
+use renumber::Numbering;
+use select::SelectList;
+use sort::{ActivePin, SortOrder};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Write};
+use std::rc::Rc;
+use substitute::Substitution;
+use templates::Template;
+use theme::Theme;
+use undo::{Change, History};
+use workspaces::Workspaces;
 use zellij_tile::prelude::*;
 
-#[derive(Default)]
-struct State {
-    tabs: Vec<TabInfo>,
-    filter: String,
-    selected: Option<usize>,
-    ignore_case: bool,
-}
+/// An action that waits for a `y` in `Mode::Confirm` before it runs.
+#[derive(Clone, Debug, PartialEq)]
+enum Confirmation {
+    KillSessions(Vec<String>),
+    CloseTabs(Vec<usize>),
+    /// Tab ids with their current and new names.
+    RenameTabs(Vec<(usize, String, String)>),
+}
+
+impl Confirmation {
+    fn prompt(&self) -> String {
+        match self {
+            Confirmation::KillSessions(names) => {
+                format!("kill {}? (y/n)", plural(names.len(), "other session"))
+            }
+            Confirmation::CloseTabs(tab_ids) => {
+                format!("close {}? (y/n)", plural(tab_ids.len(), "tab"))
+            }
+            Confirmation::RenameTabs(renames) => renames.iter().fold(
+                format!("rename {}? (y/n)", plural(renames.len(), "tab")),
+                |prompt, (_, name, renamed)| format!("{prompt}\n  {name} → {renamed}"),
+            ),
+        }
+    }
+}
+
+const RECENT_FILE: &str = "recent.json";
+const RECENT_LIMIT: usize = 100;
+const FOCUSED_FILE: &str = "focused.json";
+const SEARCHES_FILE: &str = "searches.json";
+const SELECTION_FILE: &str = "selection.json";
+const NOTES_FILE: &str = "notes.json";
+/// How many tabs of the trail the footer shows.
+const BREADCRUMB_LENGTH: usize = 5;
+
+/// A line of the tab list: a tab, the header of a group of tabs sharing a name prefix, or (below
+/// them) another session and its tabs.
+#[derive(Clone, Debug, PartialEq)]
+enum Row {
+    Group(String),
+    Tab(usize),
+    Session(String),
+    SessionTab(String, usize),
+}
+
+/// What `State::rows` was computed from, besides the inputs that only change through events or
+/// bump `rows_revision` when they do.
+struct RowsKey {
+    revision: u64,
+    filter: String,
+    options: matcher::Options,
+    sort_order: SortOrder,
+    sort_reverse: bool,
+}
+
+#[derive(Default)]
+struct State {
+    tabs: Vec<TabInfo>,
+    /// Whether a `TabUpdate` has arrived, so an empty `tabs` means no tabs rather than not yet.
+    tabs_known: bool,
+    panes: HashMap<usize, Vec<PaneInfo>>,
+    mode: Mode,
+    filter: String,
+    /// Filters from the `preset_<n>` configuration keys, applied with `F<n>`.
+    presets: BTreeMap<u8, String>,
+    /// Filters saved by name from `Mode::Search`.
+    searches: BTreeMap<String, String>,
+    selected: Option<usize>,
+    /// The selected row when it isn't one of this session's tabs.
+    highlighted: Option<Row>,
+    /// Ids of the tabs marked with `Space`; actions that support it apply to all of them.
+    marked: BTreeSet<usize>,
+    /// Renames, tag changes and marks that `u` can revert.
+    history: History,
+    /// Where room's calls to close, switch to and rename tabs go.
+    host: HostHandle,
+    /// Hides the pane instead of closing it when done, so room stays loaded and opens instantly.
+    resident: bool,
+    esc_behavior: EscBehavior,
+    /// The `action` option, until the first tab update lets it run.
+    launch_action: Option<LaunchAction>,
+    /// Draws nothing, while room only runs a launch action that needs no input.
+    silent: bool,
+    /// Whether the pane is on screen, as last reported by Zellij.
+    visible: bool,
+    /// Whether room has drawn a frame yet. A `toggle` that launched room finds it not rendered,
+    /// and must leave it shown.
+    rendered: bool,
+    pending: Vec<Pending>,
+    restore_selection: bool,
+    /// Id of the tab last selected in each session, to start from when room opens again.
+    last_selected: BTreeMap<String, usize>,
+    /// Whether the selection was restored since room opened (or there was nothing to restore).
+    restored: bool,
+    /// Id of the tab to select once `pending` has settled.
+    pending_selection: Option<usize>,
+    /// The last `rows()`, reused while navigating until one of its inputs changes.
+    row_cache: RefCell<Option<(RowsKey, Rc<[Row]>)>>,
+    rows_revision: u64,
+    /// The row under the mouse cursor.
+    hovered: Option<Row>,
+    /// Index of the first row on screen when the list is taller than the pane.
+    scroll: usize,
+    /// Rows that fit on screen at the last render, for paging.
+    page_size: usize,
+    group_separator: Option<String>,
+    collapsed: BTreeSet<String>,
+    ignore_case: bool,
+    /// Matches `e` against `é` and the like.
+    ignore_accents: bool,
+    /// Match the filter's characters in order rather than as one substring.
+    fuzzy: bool,
+    /// `Enter` in `Mode::Search` focuses the selected match instead of returning to
+    /// `Mode::Normal`.
+    search_enter_focuses: bool,
+    export_format: export::Format,
+    debug: bool,
+    layout_dump_path: String,
+    hide_plugin_tabs: bool,
+    theme: Theme,
+    icons: Icons,
+    sort_order: SortOrder,
+    sort_reverse: bool,
+    pin_active_tab: ActivePin,
+    recent: BTreeMap<String, Vec<String>>,
+    /// When each tab (by session, then name) last became active, in seconds since the epoch.
+    focused: BTreeMap<String, BTreeMap<String, i64>>,
+    show_focus_age: bool,
+    show_pane_counts: bool,
+    /// Draws the session line with its totals above everything else.
+    show_header: bool,
+    /// Draws room's version, the sort order and the filter's scope under the list.
+    show_footer: bool,
+    /// Draws the filter line in `Mode::Normal`.
+    show_mode_line: bool,
+    show_pane_titles: bool,
+    /// Seconds without focus after which a tab counts as stale.
+    stale_after: i64,
+    /// What `F` runs in its floating pane instead of a shell.
+    floating_command: Option<String>,
+    /// The folder Zellij was started in, fetched once in `load`.
+    initial_cwd: std::path::PathBuf,
+    /// Width of the pane at the last render, for modes that lay things out side by side.
+    cols: usize,
+    /// Height of the pane at the last render, for modes that fit extra panels in.
+    rows: usize,
+    /// Styles rows with colors, or marks them with plain `>` and `*` markers when `false`.
+    color: bool,
+    /// Host directory holding the per-tab scratch notes opened with `E`.
+    scratch_dir: String,
+    /// Commands from the `action_<key>` configuration keys, run in the selected tab from `!`.
+    actions: BTreeMap<char, String>,
+    /// Scripts from the `custom_action_<key>` configuration keys, run by their key in
+    /// `Mode::Normal`.
+    custom_actions: BTreeMap<char, Vec<Step>>,
+    /// Actions bound to keys in `Mode::Normal` by the `bind_<key>` configuration keys, taking
+    /// precedence over the defaults.
+    bindings: Vec<(KeyWithModifier, Action)>,
+    /// The `renumber_format` scheme, or `None` when it's invalid and renumbering is off.
+    numbering: Option<Numbering>,
+    launch_in_pane: bool,
+    name_launched_tabs: bool,
+    layouts: Vec<LayoutInfo>,
+    configured_layouts: Vec<String>,
+    /// Layout that `T` opens new tabs with, instead of Zellij's default one.
+    new_tab_layout: Option<String>,
+    templates: Vec<Template>,
+    tags: BTreeMap<String, Vec<String>>,
+    /// Colors given to tabs with `C`, by tab name.
+    labels: BTreeMap<String, labels::Label>,
+    /// Tabs switched to from room, by session, for `[` and `]` to go back and forward through.
+    trails: BTreeMap<String, trail::Trail>,
+    /// Short notes attached to tabs from `a`, by session and then tab name.
+    notes: BTreeMap<String, BTreeMap<String, String>>,
+    session_name: Option<String>,
+    show_other_sessions: bool,
+    sessions: Vec<SessionInfo>,
+    /// Other sessions' tabs matching the filter, best first, as scored by the match worker.
+    session_matches: Vec<(String, usize)>,
+    match_generation: u64,
+    /// What the match worker was last asked, to only ask again when it changes.
+    match_query: Option<(String, matcher::Options)>,
+    status: Option<String>,
+    connected_clients: usize,
+    workspaces: BTreeMap<String, Workspaces>,
+    preview: bool,
+    preview_lines: usize,
+    preview_target: Option<(usize, u32)>,
+    preview_contents: Option<PreviewResponse>,
+    /// Whether a `Timer` event to refresh the preview is on its way.
+    preview_timer: bool,
+}
+
+impl State {
+    fn log(&self, message: std::fmt::Arguments) {
+        if self.debug {
+            debug::log(message);
+        }
+    }
+
+    /// Gets room out of the way once it's done: closes it, or only hides it when resident.
+    fn dismiss(&self) {
+        if self.resident {
+            self.host.hide_plugin();
+        } else {
+            self.host.close_plugin();
+        }
+    }
+
+    fn report_error(&mut self, error: RoomError) {
+        self.log(format_args!("error {error:?}"));
+
+        self.status = Some(error.to_string());
+    }
+
+    /// Parses a configuration value, reporting it and falling back to `default` when it's invalid.
+    fn config<T: std::str::FromStr>(
+        &mut self,
+        configuration: &BTreeMap<String, String>,
+        key: &str,
+        default: T,
+    ) -> T {
+        let Some(value) = configuration.get(key) else {
+            return default;
+        };
+
+        match value.trim().parse() {
+            Ok(value) => value,
+            Err(_) => {
+                self.report_error(RoomError::Config {
+                    key: key.to_string(),
+                    value: value.clone(),
+                });
+
+                default
+            }
+        }
+    }
+
+    fn matches(&self, haystack: &str, needle: &str) -> bool {
+        matcher::score(haystack, needle, self.match_options()).is_some()
+    }
+
+    fn match_options(&self) -> matcher::Options {
+        matcher::Options {
+            ignore_case: self.ignore_case,
+            ignore_accents: self.ignore_accents,
+            fuzzy: self.fuzzy,
+        }
+    }
+
+    /// Every whitespace separated term has to match: `@label` terms match the tab's tags, `%color`
+    /// terms its color label, anything else matches its name.
+    fn filter(&self, tab: &&TabInfo) -> bool {
+        self.filter
+            .split_whitespace()
+            .all(|term| match term.strip_prefix('@') {
+                None if term == ":stale" => self.is_stale(&tab.name),
+                None if term.starts_with("cmd:") => self.runs(tab.position, &term[4..]),
+                None if term.len() > 1 && term.starts_with('%') => self
+                    .labels
+                    .get(&tab.name)
+                    .is_some_and(|label| label.is_named(&term[1..])),
+                Some(label) => self
+                    .tags
+                    .get(&tab.name)
+                    .is_some_and(|tags| tags.iter().any(|tag| self.matches(tag, label))),
+                None => self.matches(&tab.name, term),
+            })
+    }
+
+    /// Tabs outside the active workspace (and plugin-only tabs, if configured) are hidden from the
+    /// default list, but an explicit filter still reaches them.
+    fn viewable_tabs_iter(&self) -> impl Iterator<Item = &TabInfo> {
+        let workspaces = self.session_workspaces();
+
+        self.tabs.iter().filter(move |tab| {
+            if self.filter.is_empty() {
+                !workspaces.is_some_and(|workspaces| workspaces.hides(&tab.name))
+                    && (!self.hide_plugin_tabs || self.has_terminals(tab.position))
+            } else {
+                self.filter(tab)
+            }
+        })
+    }
+
+    fn viewable_tabs(&self) -> Vec<&TabInfo> {
+        let mut tabs: Vec<&TabInfo> = self.viewable_tabs_iter().collect();
+
+        match self.sort_order {
+            SortOrder::Position => (),
+            SortOrder::Alphabetical => tabs.sort_by_key(|tab| tab.name.to_lowercase()),
+            SortOrder::Recent => {
+                let recent = self.session_recent();
+
+                tabs.sort_by_key(|tab| {
+                    recent
+                        .iter()
+                        .position(|name| *name == tab.name)
+                        .unwrap_or(usize::MAX)
+                })
+            }
+            SortOrder::Panes => {
+                tabs.sort_by_key(|tab| std::cmp::Reverse(self.pane_count(tab.position)))
+            }
+        }
+
+        if self.sort_reverse {
+            tabs.reverse();
+        }
+
+        // a stable sort on whether it's the active tab moves just that one
+        match self.pin_active_tab {
+            ActivePin::None => (),
+            ActivePin::Top => tabs.sort_by_key(|tab| !tab.active),
+            ActivePin::Bottom => tabs.sort_by_key(|tab| tab.active),
+        }
+
+        tabs
+    }
+
+    /// Whether the tab has any terminal pane. Tabs we haven't seen panes for yet count as having
+    /// one, so nothing disappears before the first `PaneUpdate`.
+    fn has_terminals(&self, position: usize) -> bool {
+        self.panes
+            .get(&position)
+            .is_none_or(|panes| panes.iter().any(|pane| !pane.is_plugin))
+    }
+
+    /// The titles of the tab's terminals, indented under the tab and cut to `width` columns.
+    fn pane_titles(&self, position: usize, width: usize) -> String {
+        let titles = self
+            .panes
+            .get(&position)
+            .into_iter()
+            .flatten()
+            .filter(|pane| !pane.is_plugin && !pane.is_suppressed)
+            .map(|pane| pane.title.as_str())
+            .collect::<Vec<&str>>()
+            .join(" · ");
+
+        width::truncate(&format!("    {titles}"), width)
+    }
+
+    fn floating_count(&self, position: usize) -> usize {
+        self.panes
+            .get(&position)
+            .map(|panes| {
+                panes
+                    .iter()
+                    .filter(|pane| !pane.is_plugin && !pane.is_suppressed && pane.is_floating)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Terminal panes in the tab, floating ones included.
+    fn pane_count(&self, position: usize) -> usize {
+        self.panes
+            .get(&position)
+            .map(|panes| {
+                panes
+                    .iter()
+                    .filter(|pane| !pane.is_plugin && !pane.is_suppressed)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Tab names of the current session, most recently active first.
+    fn session_recent(&self) -> &[String] {
+        self.session_name
+            .as_ref()
+            .and_then(|session_name| self.recent.get(session_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn record_active_tab(&mut self) {
+        let Some(name) = self
+            .tabs
+            .iter()
+            .find(|tab| tab.active)
+            .map(|tab| tab.name.clone())
+        else {
+            return;
+        };
+
+        if self.session_recent().first() != Some(&name) {
+            self.record_focus(name);
+        }
+    }
+
+    /// Moves the tab to the front of the recent list and stamps when it got focus.
+    fn record_focus(&mut self, name: String) {
+        self.invalidate_rows();
+
+        let Some(session_name) = self.session_name.clone() else {
+            return;
+        };
+
+        let recent = self.recent.entry(session_name.clone()).or_default();
+        recent.retain(|recent_name| *recent_name != name);
+        recent.insert(0, name.clone());
+        recent.truncate(RECENT_LIMIT);
+
+        let focused = self.focused.entry(session_name).or_default();
+        focused.insert(name, chrono::Utc::now().timestamp());
+        focused.retain(|name, _| recent.contains(name));
+
+        storage::save(RECENT_FILE, &self.recent);
+        storage::save(FOCUSED_FILE, &self.focused);
+    }
+
+    /// Selects the tab that was selected when room last closed, once both the tabs and the session
+    /// are known.
+    fn restore_selection(&mut self) {
+        if self.restored || self.tabs.is_empty() {
+            return;
+        }
+        let Some(session_name) = &self.session_name else {
+            return;
+        };
+
+        self.restored = true;
+
+        let Some(tab_id) = self.last_selected.get(session_name) else {
+            return;
+        };
+
+        if let Some(position) = self
+            .tabs
+            .iter()
+            .find(|tab| tab.tab_id == *tab_id)
+            .map(|tab| tab.position)
+        {
+            self.select_row(Some(Row::Tab(position)));
+        }
+    }
+
+    /// Moves a selection whose tab has gone (closed by another client, say) to the tab now at the
+    /// same place in the list, or the last one, so `Enter` and `x` still act on a tab.
+    fn repair_selection(&mut self, previous_index: Option<usize>) {
+        let viewable = self.viewable_tabs();
+        let still_there = self
+            .selected
+            .is_some_and(|position| viewable.iter().any(|tab| tab.position == position));
+
+        if let (false, Some(index)) = (still_there, previous_index) {
+            self.selected = viewable
+                .get(index.min(viewable.len().saturating_sub(1)))
+                .map(|tab| tab.position);
+        }
+    }
+
+    fn save_selection(&mut self) {
+        let Some(session_name) = self.session_name.clone() else {
+            return;
+        };
+        let Some(tab_id) = self.selected_tab().map(|tab| tab.tab_id) else {
+            return;
+        };
+
+        if self.last_selected.insert(session_name, tab_id) != Some(tab_id) {
+            storage::save(SELECTION_FILE, &self.last_selected);
+        }
+    }
+
+    /// Seconds since the tab last became active, if room ever saw it focused.
+    fn focus_age(&self, name: &str) -> Option<i64> {
+        let focused_at = self.focused.get(self.session_name.as_ref()?)?.get(name)?;
+
+        Some((chrono::Utc::now().timestamp() - focused_at).max(0))
+    }
+
+    /// Whether one of the tab's terminals runs a program matching `needle`.
+    fn runs(&self, position: usize, needle: &str) -> bool {
+        self.panes.get(&position).is_some_and(|panes| {
+            panes
+                .iter()
+                .filter(|pane| !pane.is_plugin)
+                .filter_map(icons::pane_command)
+                .any(|command| self.matches(command, needle))
+        })
+    }
+
+    fn is_stale(&self, name: &str) -> bool {
+        self.focus_age(name)
+            .is_some_and(|age| age > self.stale_after)
+    }
+
+    fn group_of<'a>(&self, name: &'a str) -> Option<&'a str> {
+        let separator = self.group_separator.as_deref()?;
+
+        name.split_once(separator).map(|(group, _)| group)
+    }
+
+    fn group_size(&self, group: &str) -> usize {
+        self.viewable_tabs_iter()
+            .filter(|tab| self.group_of(&tab.name) == Some(group))
+            .count()
+    }
+
+    /// Lays the viewable tabs out as rows, gathering each group's members under its header where
+    /// the group first appears. Collapsed groups only show their header unless a filter is active.
+    fn rows(&self) -> Rc<[Row]> {
+        if let Some((key, rows)) = &*self.row_cache.borrow() {
+            if key.revision == self.rows_revision
+                && key.filter == self.filter
+                && key.options == self.match_options()
+                && key.sort_order == self.sort_order
+                && key.sort_reverse == self.sort_reverse
+            {
+                return rows.clone();
+            }
+        }
+
+        let rows: Rc<[Row]> = self.build_rows().into();
+        let key = RowsKey {
+            revision: self.rows_revision,
+            filter: self.filter.clone(),
+            options: self.match_options(),
+            sort_order: self.sort_order,
+            sort_reverse: self.sort_reverse,
+        };
+        *self.row_cache.borrow_mut() = Some((key, rows.clone()));
+
+        rows
+    }
+
+    /// Marks `rows()` as stale after changing one of its inputs other than the filter, matching
+    /// options or sort order.
+    fn invalidate_rows(&mut self) {
+        self.rows_revision += 1;
+    }
+
+    fn build_rows(&self) -> Vec<Row> {
+        let tabs = self.viewable_tabs();
+
+        let mut rows = Vec::with_capacity(tabs.len());
+        let mut seen = BTreeSet::new();
+        for tab in &tabs {
+            let Some(group) = self.group_of(&tab.name) else {
+                rows.push(Row::Tab(tab.position));
+                continue;
+            };
+
+            if !seen.insert(group) {
+                continue;
+            }
+
+            rows.push(Row::Group(group.to_string()));
+
+            if self.filter.is_empty() && self.collapsed.contains(group) {
+                continue;
+            }
+
+            rows.extend(
+                tabs.iter()
+                    .filter(|tab| self.group_of(&tab.name) == Some(group))
+                    .map(|tab| Row::Tab(tab.position)),
+            );
+        }
+
+        if self.show_other_sessions {
+            for session in self.other_sessions() {
+                let positions: Vec<usize> = if self.filter.is_empty() {
+                    session.tabs.iter().map(|tab| tab.position).collect()
+                } else {
+                    self.session_matches
+                        .iter()
+                        .filter(|(session_name, _)| *session_name == session.name)
+                        .map(|(_, position)| *position)
+                        .collect()
+                };
+                if positions.is_empty() && !self.filter.is_empty() {
+                    continue;
+                }
+
+                rows.push(Row::Session(session.name.clone()));
+                rows.extend(
+                    positions
+                        .into_iter()
+                        .map(|position| Row::SessionTab(session.name.clone(), position)),
+                );
+            }
+        }
+
+        rows
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.highlighted
+            .clone()
+            .or_else(|| self.selected.map(Row::Tab))
+    }
+
+    fn select_row(&mut self, row: Option<Row>) {
+        match row {
+            Some(Row::Tab(position)) => {
+                self.highlighted = None;
+                self.selected = Some(position);
+            }
+            row => {
+                self.highlighted = row;
+                self.selected = None;
+            }
+        }
+    }
+
+    fn reset_selection(&mut self) {
+        let first = self.rows().first().cloned();
+
+        self.select_row(first);
+    }
+
+    fn select_down(&mut self) {
+        let rows = self.rows();
+        let mut list = SelectList::new(self.selected_row());
+
+        list.next(&rows);
+        self.select_row(list.into_selected());
+    }
+
+    fn select_up(&mut self) {
+        let rows = self.rows();
+        let mut list = SelectList::new(self.selected_row());
+
+        list.previous(&rows);
+        self.select_row(list.into_selected());
+    }
+
+    /// The row drawn on `line` of the plugin pane, when the tab list is on screen.
+    fn row_at(&self, line: isize) -> Option<Row> {
+        if !self.mode.shows_tabs() {
+            return None;
+        }
+
+        let mut line = usize::try_from(line)
+            .ok()?
+            .checked_sub(self.list_offset())?;
+
+        self.rows()
+            .iter()
+            .skip(self.scroll)
+            .find(|row| {
+                let lines = self.row_lines(row);
+                let found = line < lines;
+                line = line.saturating_sub(lines);
+                found
+            })
+            .cloned()
+    }
+
+    /// Lines a row takes on screen: tabs get a second one for their pane titles when enabled.
+    fn row_lines(&self, row: &Row) -> usize {
+        match row {
+            Row::Tab(_) if self.show_pane_titles => 2,
+            _ => 1,
+        }
+    }
+
+    /// How many rows from `start` fit in `height` lines (always at least one).
+    fn rows_fitting(&self, rows: &[Row], start: usize, height: usize) -> usize {
+        let mut lines = 0;
+
+        rows[start.min(rows.len())..]
+            .iter()
+            .take_while(|row| {
+                lines += self.row_lines(row);
+                lines <= height
+            })
+            .count()
+            .max(1)
+    }
+
+    /// Lines above the tab list: the session header (once known) and the filter line.
+    fn list_offset(&self) -> usize {
+        usize::from(self.shows_header()) + usize::from(self.shows_mode_line())
+    }
+
+    /// `show_mode_line false` only leaves the filter line out while nothing is being typed or
+    /// picked and no filter narrows the list.
+    fn shows_mode_line(&self) -> bool {
+        self.show_mode_line || !matches!(self.mode, Mode::Normal(_)) || !self.filter.is_empty()
+    }
+
+    fn shows_header(&self) -> bool {
+        self.show_header && self.session_name.is_some()
+    }
+
+    /// Lines left for the tab list once the header, status and preview are drawn.
+    fn list_height(&self, rows: usize) -> usize {
+        let status = usize::from(self.status.is_some())
+            + usize::from(self.breadcrumb().is_some())
+            + usize::from(self.show_footer);
+        let preview = if self.preview {
+            self.preview_lines + 1
+        } else {
+            0
+        };
+
+        rows.saturating_sub(self.list_offset() + status + preview)
+            .max(1)
+    }
+
+    /// Moves the viewport just enough to keep the selected row on screen.
+    fn scroll_to_selection(&mut self, rows: &[Row], height: usize) {
+        self.scroll = SelectList::new(self.selected_row()).scroll(rows, self.scroll, |start| {
+            self.rows_fitting(rows, start, height)
+        });
+    }
+
+    /// Draws a scrollbar on the last column next to the list, using absolute cursor moves so it
+    /// doesn't depend on how wide the rows are.
+    fn render_scrollbar(
+        &self,
+        frame: &mut String,
+        total: usize,
+        visible: usize,
+        height: usize,
+        cols: usize,
+    ) -> fmt::Result {
+        if total <= visible || cols == 0 {
+            return Ok(());
+        }
+
+        let thumb = (height * visible / total).max(1);
+        let start = self.scroll * (height - thumb) / (total - visible);
+
+        for line in 0..height {
+            let cell = if (start..start + thumb).contains(&line) {
+                "┃".to_string()
+            } else {
+                "│".dimmed().to_string()
+            };
+
+            write!(
+                frame,
+                "\u{1b}[{};{}H{cell}",
+                self.list_offset() + line + 1,
+                cols
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn click_row(&mut self, row: Row) {
+        self.select_row(Some(row));
+
+        if self.highlighted.is_some() {
+            self.activate_highlighted();
+        } else {
+            self.focus_selected_tab();
+        }
+    }
+
+    /// Moves the selection by `offset` rows without wrapping around.
+    fn select_by(&mut self, offset: isize) {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return;
+        }
+
+        let mut list = SelectList::new(self.selected_row());
+
+        list.move_by(&rows, offset);
+        self.select_row(list.into_selected());
+    }
+
+    /// `PageUp`/`PageDown` and `Home`/`End` in the modes showing the tab list.
+    fn page(&mut self, key: BareKey) {
+        let page = self.page_size.max(1) as isize;
+
+        match key {
+            BareKey::PageDown => self.select_by(page),
+            BareKey::PageUp => self.select_by(-page),
+            BareKey::Home => self.select_by(isize::MIN),
+            _ => self.select_by(isize::MAX),
+        }
+    }
+
+    fn other_sessions(&self) -> impl Iterator<Item = &SessionInfo> {
+        self.sessions
+            .iter()
+            .filter(|session| !session.is_current_session)
+    }
+
+    fn kill_session(&mut self, index: usize) {
+        let Some(session) = self.sessions.get(index) else {
+            return;
+        };
+
+        if session.is_current_session {
+            self.status = Some("refusing to kill the session you're attached to".to_string());
+            return;
+        }
+
+        self.log(format_args!("action kill session {}", session.name));
+
+        if let Err(message) = kill_sessions(&[&session.name]) {
+            let action = format!("kill {}", session.name);
+
+            self.report_error(RoomError::Zellij { action, message });
+        }
+    }
+
+    fn confirm(&mut self, confirmation: Confirmation) {
+        self.mode = Mode::Confirm(Confirm {
+            confirmation,
+            previous: Box::new(self.mode.clone()),
+        });
+    }
+
+    fn run_confirmation(&mut self, confirmation: Confirmation) {
+        match confirmation {
+            Confirmation::KillSessions(names) => {
+                self.log(format_args!("action kill sessions {names:?}"));
+
+                if let Err(message) = kill_sessions(&names) {
+                    self.report_error(RoomError::Zellij {
+                        action: "kill sessions".to_string(),
+                        message,
+                    });
+                }
+            }
+            Confirmation::CloseTabs(tab_ids) => {
+                self.close_tabs(tab_ids);
+                self.marked.clear();
+            }
+            Confirmation::RenameTabs(renames) => self.rename_tabs(renames),
+        }
+    }
+
+    fn run_launch_action(&mut self, action: LaunchAction) {
+        self.log(format_args!("launch action {action:?}"));
+
+        // until an update names the active tab, keep the action for the next one: room stays
+        // silent meanwhile, so dropping it would leave an invisible plugin open
+        let Some(active) = self.tabs.iter().find(|tab| tab.active) else {
+            if let LaunchAction::NewNamed(name) = action {
+                self.dismiss();
+                new_tab(Some(name.as_str()), None);
+            } else {
+                self.launch_action = Some(action);
+            }
+
+            return;
+        };
+
+        match action {
+            LaunchAction::RenameCurrent => {
+                self.mode = Mode::Rename(Rename {
+                    input: TextInput::new(&active.name),
+                    tab_id: active.tab_id,
+                    name: active.name.clone(),
+                });
+            }
+            LaunchAction::DeleteCurrent => {
+                let tab_id = active.tab_id;
+
+                self.dismiss();
+                self.host.close_tab(tab_id);
+            }
+            LaunchAction::NewNamed(name) => {
+                self.dismiss();
+                new_tab(Some(name.as_str()), None);
+            }
+        }
+    }
+
+    /// Renames tabs, given their ids with their current and new names, so that `u` can revert it.
+    fn rename_tabs(&mut self, renames: Vec<(usize, String, String)>) {
+        self.log(format_args!("action rename tabs {renames:?}"));
+
+        for (tab_id, _, renamed) in &renames {
+            self.host.rename_tab(*tab_id, renamed);
+        }
+
+        self.history.record(Change::Rename(
+            renames
+                .iter()
+                .map(|(tab_id, name, _)| (*tab_id, name.clone()))
+                .collect(),
+        ));
+        self.rename_notes(
+            renames
+                .iter()
+                .map(|(_, name, renamed)| (name.as_str(), renamed.as_str())),
+        );
+
+        self.pending_selection = self.selected_tab().map(|tab| tab.tab_id);
+        self.pending.push(Pending::Rename(
+            renames
+                .into_iter()
+                .map(|(tab_id, _, renamed)| (tab_id, renamed))
+                .collect(),
+        ));
+    }
+
+    /// Closes the tabs and, once Zellij confirms it, selects the first tab left below the
+    /// selection (or above it when the selection was at the bottom).
+    fn close_tabs(&mut self, tab_ids: Vec<usize>) {
+        self.log(format_args!("action close tabs {tab_ids:?}"));
+
+        for tab_id in &tab_ids {
+            self.host.close_tab(*tab_id);
+        }
+
+        let surviving: Vec<usize> = self
+            .rows()
+            .iter()
+            .filter_map(|row| match row {
+                Row::Tab(position) => self.tabs.iter().find(|tab| tab.position == *position),
+                _ => None,
+            })
+            .map(|tab| tab.tab_id)
+            .collect();
+        let current = self
+            .selected_tab()
+            .and_then(|selected| {
+                surviving
+                    .iter()
+                    .position(|tab_id| *tab_id == selected.tab_id)
+            })
+            .unwrap_or(0);
+
+        self.pending_selection = surviving[current..]
+            .iter()
+            .chain(surviving[..current].iter().rev())
+            .find(|tab_id| !tab_ids.contains(tab_id))
+            .copied();
+        self.pending.push(Pending::Close(tab_ids));
+    }
+
+    /// The tabs the typed substitution would rename, with their current and new names.
+    fn substitution_renames(
+        &self,
+        expression: &str,
+    ) -> Result<Vec<(usize, String, String)>, String> {
+        let substitution = Substitution::parse(expression)?;
+
+        Ok(self
+            .tabs
+            .iter()
+            .filter_map(|tab| {
+                let renamed = substitution.apply(&tab.name)?;
+
+                Some((tab.tab_id, tab.name.clone(), renamed))
+            })
+            .collect())
+    }
+
+    /// Whether the tab has terminals and every one of them has exited.
+    fn has_only_exited_terminals(&self, position: usize) -> bool {
+        let mut terminals = self
+            .panes
+            .get(&position)
+            .into_iter()
+            .flatten()
+            .filter(|pane| !pane.is_plugin)
+            .peekable();
+
+        terminals.peek().is_some() && terminals.all(|pane| pane.exited)
+    }
+
+    fn cleanup_exited_tabs(&mut self) {
+        let exited: Vec<&TabInfo> = self
+            .tabs
+            .iter()
+            .filter(|tab| self.has_only_exited_terminals(tab.position))
+            .collect();
+
+        if exited.is_empty() {
+            self.status = Some("no tabs with only exited panes".to_string());
+            return;
+        }
+
+        let status = format!(
+            "closed {}: {}",
+            plural(exited.len(), "exited tab"),
+            exited
+                .iter()
+                .map(|tab| tab.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+
+        let tab_ids = exited.iter().map(|tab| tab.tab_id).collect();
+        self.close_tabs(tab_ids);
+        self.status = Some(status);
+    }
+
+    fn renumber_tabs(&mut self) {
+        let Some(numbering) = &self.numbering else {
+            self.status = Some("renumbering is off: renumber_format is invalid".to_string());
+            return;
+        };
+        let renames: Vec<(usize, String, String)> = self
+            .tabs
+            .iter()
+            .filter_map(|tab| {
+                let renamed = numbering.apply(tab.position, &tab.name)?;
+
+                Some((tab.tab_id, tab.name.clone(), renamed))
+            })
+            .collect();
+
+        if renames.is_empty() {
+            self.status = Some("tabs are already numbered".to_string());
+        } else {
+            self.confirm(Confirmation::RenameTabs(renames));
+        }
+    }
+
+    /// Lets the current mode handle the key. The handler works on a copy of the mode, which
+    /// replaces the mode afterwards unless the handler switched to another one.
+    fn handle_key(&mut self, key: KeyWithModifier) -> bool {
+        let mut mode = self.mode.clone();
+        let should_render = mode.handler_mut().handle_key(self, key);
+
+        if std::mem::discriminant(&mode) == std::mem::discriminant(&self.mode) {
+            self.mode = mode;
+        }
+
+        should_render
+    }
+
+    fn toggle_group(&mut self, group: &str) {
+        self.invalidate_rows();
+
+        if !self.collapsed.remove(group) {
+            self.collapsed.insert(group.to_string());
+        }
+    }
+
+    /// `Enter` on anything but one of this session's tabs.
+    fn activate_highlighted(&mut self) {
+        match self.highlighted.clone() {
+            Some(Row::Group(group)) => self.toggle_group(&group),
+            Some(Row::Session(session_name)) => {
+                self.log(format_args!("action switch to session {session_name}"));
+                self.dismiss();
+                switch_session(Some(&session_name));
+            }
+            Some(Row::SessionTab(session_name, position)) => {
+                self.log(format_args!(
+                    "action switch to session {session_name}, tab {position}"
+                ));
+                self.dismiss();
+                switch_session_with_focus(&session_name, Some(position), None);
+            }
+            Some(Row::Tab(_)) | None => (),
+        }
+    }
+
+    fn selected_tab(&self) -> Option<&TabInfo> {
+        self.tabs
+            .iter()
+            .find(|tab| Some(tab.position) == self.selected)
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(tab_id) = self.selected_tab().map(|tab| tab.tab_id) else {
+            return;
+        };
+
+        self.history.record(Change::Marks(self.marked.clone()));
+
+        if !self.marked.remove(&tab_id) {
+            self.marked.insert(tab_id);
+        }
+    }
+
+    /// This session's tabs between the visual anchor and the selection, in list order.
+    fn visual_range(&self) -> Vec<&TabInfo> {
+        let Mode::Visual(visual) = &self.mode else {
+            return Vec::new();
+        };
+
+        let rows = self.rows();
+        let selected_row = self.selected_row();
+        let position = |row: Option<&Row>| rows.iter().position(|candidate| Some(candidate) == row);
+
+        let (Some(anchor), Some(current)) = (
+            position(Some(&visual.anchor)),
+            position(selected_row.as_ref()),
+        ) else {
+            return Vec::new();
+        };
+
+        rows[anchor.min(current)..=anchor.max(current)]
+            .iter()
+            .filter_map(|row| match row {
+                Row::Tab(position) => self.tabs.iter().find(|tab| tab.position == *position),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The tabs an action applies to: the visual range, the marked tabs, or the selected one when
+    /// nothing is marked.
+    fn target_tabs(&self) -> Vec<&TabInfo> {
+        if matches!(self.mode, Mode::Visual(_)) {
+            self.visual_range()
+        } else if self.marked.is_empty() {
+            self.selected_tab().into_iter().collect()
+        } else {
+            self.tabs
+                .iter()
+                .filter(|tab| self.marked.contains(&tab.tab_id))
+                .collect()
+        }
+    }
+
+    /// The pane that best represents a tab: its focused terminal, preferring the tiled layer.
+    fn focused_terminal(&self, position: usize) -> Option<u32> {
+        let panes = self.panes.get(&position)?;
+        let candidates = || {
+            panes
+                .iter()
+                .filter(|pane| !pane.is_plugin && !pane.is_suppressed && pane.is_focused)
+        };
+
+        candidates()
+            .find(|pane| !pane.is_floating)
+            .or_else(|| candidates().next())
+            .map(|pane| pane.id)
+    }
+
+    /// The working directory of the tab's focused terminal, or the one Zellij started room in.
+    fn tab_cwd(&self, position: usize) -> std::path::PathBuf {
+        self.focused_terminal(position)
+            .and_then(|pane_id| get_pane_cwd(PaneId::Terminal(pane_id)).ok())
+            .unwrap_or_else(|| self.initial_cwd.clone())
+    }
+
+    /// Switches to the selected tab and opens a terminal there, floating or tiled.
+    fn open_pane_in_selected_tab(&mut self, floating: bool) {
+        let Some(position) = self.selected_tab().map(|tab| tab.position) else {
+            return;
+        };
+        let cwd = self.tab_cwd(position);
+
+        self.log(format_args!(
+            "action open {} pane in tab {position}",
+            if floating { "floating" } else { "tiled" }
+        ));
+        self.dismiss();
+        self.host.focus_tab(position);
+
+        if !floating {
+            open_terminal(cwd);
+            return;
+        }
+
+        let mut words = self
+            .floating_command
+            .iter()
+            .flat_map(|command| command.split_whitespace());
+        match words.next() {
+            Some(program) => {
+                let command_to_run = CommandToRun {
+                    path: program.into(),
+                    args: words.map(String::from).collect(),
+                    cwd: Some(cwd),
+                };
+
+                open_command_pane_floating(command_to_run, None, BTreeMap::new());
+            }
+            None => {
+                open_terminal_floating(cwd, None);
+            }
+        }
+    }
+
+    /// Runs the command in a new pane of the selected tab, in the working directory of its focused
+    /// pane.
+    fn run_in_selected_tab(&mut self, command: &str) {
+        let Some(position) = self.selected_tab().map(|tab| tab.position) else {
+            return;
+        };
+
+        let mut words = command.split_whitespace();
+        let Some(program) = words.next() else {
+            return;
+        };
+
+        let command_to_run = CommandToRun {
+            path: program.into(),
+            args: words.map(String::from).collect(),
+            cwd: Some(self.tab_cwd(position)),
+        };
+
+        self.log(format_args!("action run {command:?} in tab {position}"));
+        self.dismiss();
+        self.host.focus_tab(position);
+        self.host.open_command_pane(command_to_run);
+    }
+
+    fn run_custom_action(&mut self, c: char) {
+        let Some(tab) = self.selected_tab() else {
+            return;
+        };
+        let (position, tab_id, name) = (tab.position, tab.tab_id, tab.name.clone());
+        let cwd = self.tab_cwd(position);
+        let Some(steps) = self.custom_actions.get(&c) else {
+            return;
+        };
+
+        self.log(format_args!(
+            "action custom {c} {steps:?} in tab {position}"
+        ));
+
+        for step in steps {
+            match step {
+                Step::Focus => self.host.focus_tab(position),
+                Step::Pane => {
+                    open_terminal(&cwd);
+                }
+                Step::Floating => {
+                    open_terminal_floating(&cwd, None);
+                }
+                Step::Run(command) => {
+                    let mut words = command.split_whitespace();
+
+                    if let Some(program) = words.next() {
+                        self.host.open_command_pane(CommandToRun {
+                            path: program.into(),
+                            args: words.map(String::from).collect(),
+                            cwd: Some(cwd.clone()),
+                        });
+                    }
+                }
+                // applied after the other steps, as one rename like any other
+                Step::Rename(_) => (),
+            }
+        }
+
+        let leaves_room = steps.iter().any(Step::leaves_room);
+        let renamed = steps.iter().rev().find_map(|step| match step {
+            Step::Rename(format) => Some(format.replace("{name}", &name)),
+            _ => None,
+        });
+
+        if let Some(renamed) = renamed.filter(|renamed| *renamed != name) {
+            self.rename_tabs(vec![(tab_id, name, renamed)]);
+        }
+
+        if leaves_room {
+            self.dismiss();
+        }
+    }
+
+    /// Opens `$EDITOR` on the selected tab's scratch file in a new pane of that tab. The editor runs
+    /// on the host, so the file lives in `scratch_dir` rather than room's sandboxed data dir.
+    fn open_scratch(&mut self) {
+        let Some(tab) = self.selected_tab() else {
+            return;
+        };
+        let position = tab.position;
+        let file: String = tab
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+
+        // the directory is passed as an argument rather than spliced into the script, so spaces or
+        // shell syntax in it stay part of the path; the script expands a leading `~` itself, then
+        // `$EDITOR`, and creates the directory on first use
+        let command_to_run = CommandToRun {
+            path: "sh".into(),
+            args: vec![
+                "-c".to_string(),
+                concat!(
+                    r#"dir=$1; case $dir in "~" | "~/"*) dir=$HOME${dir#"~"} ;; esac; "#,
+                    r#"mkdir -p "$dir" && exec ${EDITOR:-vi} "$dir/$2.md""#,
+                )
+                .to_string(),
+                "sh".to_string(),
+                self.scratch_dir.clone(),
+                file.clone(),
+            ],
+            cwd: Some(self.tab_cwd(position)),
+        };
+
+        self.log(format_args!(
+            "action open scratch {file}.md in tab {position}"
+        ));
+        self.dismiss();
+        self.host.focus_tab(position);
+        self.host.open_command_pane(command_to_run);
+    }
+
+    fn new_tab_in_current_cwd(&self) {
+        let cwd = self
+            .tabs
+            .iter()
+            .find(|tab| tab.active)
+            .and_then(|tab| self.focused_terminal(tab.position))
+            .and_then(|pane_id| get_pane_cwd(PaneId::Terminal(pane_id)).ok());
+
+        self.log(format_args!("action new tab in {cwd:?}"));
+        new_tab(None, cwd.map(|cwd| cwd.to_string_lossy().into_owned()));
+        self.dismiss();
+    }
+
+    fn tab_summaries(&self) -> Vec<export::TabSummary> {
+        self.tabs
+            .iter()
+            .map(|tab| export::TabSummary {
+                position: tab.position,
+                name: tab.name.clone(),
+                active: tab.active,
+                panes: self.pane_count(tab.position),
+                cwd: self
+                    .focused_terminal(tab.position)
+                    .and_then(|pane_id| get_pane_cwd(PaneId::Terminal(pane_id)).ok())
+                    .map(|cwd| cwd.to_string_lossy().into_owned()),
+            })
+            .collect()
+    }
+
+    fn export_tabs(&self) {
+        let tabs = self.tab_summaries();
+
+        export::open_in_pane(match self.export_format {
+            export::Format::Text => export::to_text(&tabs),
+            export::Format::Json => export::to_json(&tabs),
+        });
+        self.dismiss();
+    }
+
+    /// Writes the session layout under `/host`, which Zellij maps to the folder it was started in.
+    fn dump_layout(&mut self) {
+        let result = host_path(&self.layout_dump_path)
+            .and_then(|path| {
+                dump_session_layout()
+                    .map(|(layout, _)| (path, layout))
+                    .map_err(|message| RoomError::Zellij {
+                        action: "dump the session layout".to_string(),
+                        message,
+                    })
+            })
+            .and_then(|(path, layout)| {
+                std::fs::write(&path, layout).map_err(|error| RoomError::Io {
+                    operation: "write".to_string(),
+                    path: self.layout_dump_path.clone(),
+                    message: error.to_string(),
+                })
+            });
+
+        match result {
+            Ok(()) => self.status = Some(format!("layout saved to {}", self.layout_dump_path)),
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    /// Writes the selected tab's part of the session layout to `<tab name>.kdl` under `/host`, to
+    /// reuse a tab that grew by hand as a layout.
+    fn dump_tab_layout(&mut self) {
+        let Some(name) = self.selected_tab().map(|tab| tab.name.clone()) else {
+            return;
+        };
+
+        let file = format!(
+            "{}.kdl",
+            name.replace(|c: char| c == '/' || c.is_whitespace(), "-")
+        );
+
+        let result = dump_session_layout()
+            .map_err(|message| RoomError::Zellij {
+                action: "dump the session layout".to_string(),
+                message,
+            })
+            .and_then(|(layout, _)| {
+                export::tab_layout(&layout, &name)
+                    .ok_or_else(|| RoomError::Input(format!("{name} isn't in the session layout")))
+            })
+            .and_then(|layout| Ok((host_path(&file)?, layout)))
+            .and_then(|(path, layout)| {
+                std::fs::write(path, layout).map_err(|error| RoomError::Io {
+                    operation: "write".to_string(),
+                    path: file.clone(),
+                    message: error.to_string(),
+                })
+            });
+
+        match result {
+            Ok(()) => self.status = Some(format!("{name} layout saved to {file}")),
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    /// Writes everything room persists to a JSON file under `/host`, for `import_data` to load on
+    /// another machine.
+    fn export_data(&mut self, path: &str) {
+        let contents = serde_json::to_string_pretty(&backup::Backup::collect()).unwrap_or_default();
+
+        let result = host_path(path).and_then(|host_path| {
+            std::fs::write(host_path, contents).map_err(|error| RoomError::Io {
+                operation: "write".to_string(),
+                path: path.to_string(),
+                message: error.to_string(),
+            })
+        });
+
+        match result {
+            Ok(()) => self.status = Some(format!("data exported to {path}")),
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    /// Replaces everything room persists with a file written by `export_data`.
+    fn import_data(&mut self, path: &str) {
+        let backup = host_path(path)
+            .and_then(|host_path| {
+                std::fs::read_to_string(host_path).map_err(|error| RoomError::Io {
+                    operation: "read".to_string(),
+                    path: path.to_string(),
+                    message: error.to_string(),
+                })
+            })
+            .and_then(|contents| {
+                serde_json::from_str::<backup::Backup>(&contents)
+                    .map_err(|error| RoomError::Input(format!("can't import {path}: {error}")))
+            });
+
+        let backup = match backup {
+            Ok(backup) => backup,
+            Err(error) => {
+                self.report_error(error);
+                return;
+            }
+        };
+
+        backup.restore();
+
+        self.tags = backup.tags;
+        self.labels = backup.labels;
+        self.notes = backup.notes;
+        self.trails = backup.trails;
+        self.workspaces = backup.workspaces;
+        self.recent = backup.recent;
+        self.focused = backup.focused;
+        self.searches = backup.searches;
+        if self.restore_selection {
+            self.last_selected = backup.selection;
+        }
+
+        self.invalidate_rows();
+        self.status = Some(format!("data imported from {path}"));
+    }
+
+    fn focus_selected_tab(&mut self) {
+        let Some((position, name)) = self
+            .selected_tab()
+            .map(|tab| (tab.position, tab.name.clone()))
+        else {
+            return;
+        };
+
+        self.log(format_args!("action focus tab {position} ({name})"));
+        self.record_trail(&name);
+        self.record_focus(name);
+        self.dismiss();
+        self.host.focus_tab(position);
+    }
+
+    /// Adds a switch from the active tab to `name` to the session's trail.
+    fn record_trail(&mut self, name: &str) {
+        let Some(active) = self.tabs.iter().find(|tab| tab.active) else {
+            return;
+        };
+        let Some(session_name) = self.session_name.clone() else {
+            return;
+        };
+
+        if active.name != name {
+            let from = active.name.clone();
+
+            self.trails
+                .entry(session_name)
+                .or_default()
+                .visit(&from, name);
+            storage::save(trail::FILE, &self.trails);
+        }
+    }
+
+    /// Switches to the tab before (or after) the current one in the session's trail, skipping tabs
+    /// that have closed since.
+    fn walk_trail(&mut self, forward: bool) {
+        let tabs = &self.tabs;
+        let can_visit = |name: &str| tabs.iter().any(|tab| !tab.active && tab.name == name);
+
+        let target = self
+            .session_name
+            .as_ref()
+            .and_then(|session_name| self.trails.get_mut(session_name))
+            .and_then(|trail| {
+                if forward {
+                    trail.forward(can_visit)
+                } else {
+                    trail.back(can_visit)
+                }
+            })
+            .and_then(|name| tabs.iter().find(|tab| tab.name == name));
+
+        let Some((position, name)) = target.map(|tab| (tab.position, tab.name.clone())) else {
+            self.status = Some("no tab to go to".to_string());
+            return;
+        };
+
+        storage::save(trail::FILE, &self.trails);
+
+        self.log(format_args!("action walk trail to {position} ({name})"));
+        self.record_focus(name);
+        self.dismiss();
+        self.host.focus_tab(position);
+    }
+
+    /// Switches to the most recently active tab besides the current one, like `cd -`.
+    fn focus_previous_tab(&mut self) {
+        let previous = self.session_recent().iter().find_map(|name| {
+            self.tabs
+                .iter()
+                .find(|tab| !tab.active && tab.name == *name)
+                .map(|tab| (tab.position, tab.name.clone()))
+        });
+
+        let Some((position, name)) = previous else {
+            self.status = Some("no previous tab".to_string());
+            return;
+        };
+
+        self.log(format_args!(
+            "action focus previous tab {position} ({name})"
+        ));
+        self.record_trail(&name);
+        self.record_focus(name);
+        self.dismiss();
+        self.host.focus_tab(position);
+    }
+
+    fn launch_command(&mut self, command: &str) {
+        let mut words = command.split_whitespace();
+        let Some(program) = words.next() else {
+            return;
+        };
+
+        let command_to_run = CommandToRun {
+            path: program.into(),
+            args: words.map(String::from).collect(),
+            cwd: None,
+        };
+
+        self.log(format_args!(
+            "action launch {:?}{}",
+            command,
+            if self.launch_in_pane { " in pane" } else { "" }
+        ));
+
+        if self.launch_in_pane {
+            let Some(position) = self.selected_tab().map(|tab| tab.position) else {
+                return;
+            };
+
+            self.host.focus_tab(position);
+            self.host.open_command_pane(command_to_run);
+        } else {
+            let (tab_id, _) = open_command_pane_in_new_tab(command_to_run, BTreeMap::new());
+
+            if let Some(tab_id) = tab_id.filter(|_| self.name_launched_tabs) {
+                self.host.rename_tab(tab_id, command.trim());
+            }
+        }
+
+        self.dismiss();
+    }
+
+    fn apply_preset(&mut self, number: u8) -> bool {
+        let Some(preset) = self.presets.get(&number) else {
+            return false;
+        };
+
+        self.filter = preset.clone();
+        self.reset_selection();
+        true
+    }
+
+    /// Type-ahead: selects the next tab after the selection whose name starts with `c`.
+    fn jump_to_initial(&mut self, c: char) -> bool {
+        let rows = self.rows();
+        let current = self.selected_row();
+        let start = rows
+            .iter()
+            .position(|row| Some(row) == current.as_ref())
+            .map_or(0, |index| index + 1);
+
+        let next = rows[start..]
+            .iter()
+            .chain(&rows[..start])
+            .find(|row| match row {
+                Row::Tab(position) => self
+                    .tabs
+                    .iter()
+                    .find(|tab| tab.position == *position)
+                    .is_some_and(|tab| self.matches(&tab.name, &format!("^{c}"))),
+                _ => false,
+            })
+            .cloned();
+
+        if next.is_some() {
+            self.select_row(next);
+        }
+
+        true
+    }
+
+    /// Opens `Mode::Tag` for the target tabs, prefilled with the tags they currently share.
+    fn start_tagging(&mut self) -> bool {
+        let targets: Vec<String> = self
+            .target_tabs()
+            .iter()
+            .map(|tab| tab.name.clone())
+            .collect();
+
+        let Some(first) = targets.first() else {
+            return false;
+        };
+
+        let tags = self.tags.get(first).cloned().unwrap_or_default();
+        let input = if targets
+            .iter()
+            .all(|name| self.tags.get(name) == self.tags.get(first))
+        {
+            tags.join(" ")
+        } else {
+            String::new()
+        };
+
+        self.mode = Mode::Tag(Tag {
+            input: TextInput::new(&input),
+            targets,
+        });
+        true
+    }
+
+    fn layout_choices(&self) -> Vec<LayoutInfo> {
+        if self.configured_layouts.is_empty() {
+            return self.layouts.clone();
+        }
+
+        self.configured_layouts
+            .iter()
+            .map(|name| self.find_layout(name))
+            .collect()
+    }
+
+    /// The layout Zellij discovered under `name`, or else a layout file by that name.
+    fn find_layout(&self, name: &str) -> LayoutInfo {
+        self.layouts
+            .iter()
+            .find(|layout| layout.name() == name)
+            .cloned()
+            .unwrap_or_else(|| LayoutInfo::File(name.to_string(), LayoutMetadata::default()))
+    }
+
+    fn new_tab_from_layout(&self) {
+        self.log(format_args!(
+            "action new tab with {:?}",
+            self.new_tab_layout
+        ));
+
+        match &self.new_tab_layout {
+            Some(name) => {
+                new_tabs_with_layout_info(self.find_layout(name));
+            }
+            None => {
+                new_tab::<&str>(None, None);
+            }
+        }
+
+        self.dismiss();
+    }
+
+    fn session_workspaces(&self) -> Option<&Workspaces> {
+        self.workspaces.get(self.session_name.as_ref()?)
+    }
+
+    fn tab_note(&self, tab_name: &str) -> Option<&String> {
+        self.notes.get(self.session_name.as_ref()?)?.get(tab_name)
+    }
+
+    /// Opens `Mode::Note` for the selected tab, prefilled with its note.
+    fn start_note(&mut self) -> bool {
+        let Some(target) = self.selected_tab().map(|tab| tab.name.clone()) else {
+            return false;
+        };
+        let input = TextInput::new(self.tab_note(&target).map_or("", String::as_str));
+
+        self.mode = Mode::Note(Note { input, target });
+        true
+    }
+
+    fn save_note(&mut self, tab_name: &str, note: &str) {
+        let Some(session_name) = self.session_name.clone() else {
+            return;
+        };
+        self.invalidate_rows();
+
+        let notes = self.notes.entry(session_name).or_default();
+        match note.trim() {
+            "" => notes.remove(tab_name),
+            note => notes.insert(tab_name.to_string(), note.to_string()),
+        };
+
+        storage::save(NOTES_FILE, &self.notes);
+    }
+
+    /// Moves notes over to the new names of tabs room renamed.
+    fn rename_notes<'a>(&mut self, renames: impl IntoIterator<Item = (&'a str, &'a str)>) {
+        let Some(notes) = self
+            .session_name
+            .as_ref()
+            .and_then(|session_name| self.notes.get_mut(session_name))
+        else {
+            return;
+        };
+
+        let moved: Vec<(String, String)> = renames
+            .into_iter()
+            .filter_map(|(name, renamed)| Some((renamed.to_string(), notes.remove(name)?)))
+            .collect();
+
+        if !moved.is_empty() {
+            notes.extend(moved);
+            storage::save(NOTES_FILE, &self.notes);
+        }
+    }
+
+    fn session_workspaces_mut(&mut self) -> Option<&mut Workspaces> {
+        let session_name = self.session_name.clone()?;
+        self.invalidate_rows();
+
+        Some(self.workspaces.entry(session_name).or_default())
+    }
+
+    /// The rows of the workspace picker: `None` stands for "all tabs".
+    fn workspace_choices(&self) -> Vec<Option<String>> {
+        let names: Vec<Option<String>> = self
+            .session_workspaces()
+            .map(|workspaces| workspaces.members.keys().cloned().map(Some).collect())
+            .unwrap_or_default();
+
+        [None].into_iter().chain(names).collect()
+    }
+
+    fn activate_workspace(&mut self, workspace: Option<String>) {
+        let Some(workspaces) = self.session_workspaces_mut() else {
+            return;
+        };
+
+        workspaces.active = workspace;
+
+        let first_member = workspaces
+            .active
+            .as_ref()
+            .and_then(|active| workspaces.members.get(active))
+            .cloned()
+            .unwrap_or_default();
+
+        storage::save(workspaces::FILE, &self.workspaces);
+
+        let first_tab = self
+            .tabs
+            .iter()
+            .find(|tab| first_member.contains(&tab.name))
+            .map(|tab| tab.position);
+
+        if let Some(position) = first_tab {
+            self.log(format_args!("action focus tab {position}"));
+            self.dismiss();
+            self.host.focus_tab(position);
+        } else {
+            self.reset_selection();
+            self.mode = Mode::Normal(Normal);
+        }
+    }
+
+    /// Gives the target tabs the color after the first one's, or takes it away after the last.
+    fn cycle_label(&mut self) -> bool {
+        let targets: Vec<String> = self
+            .target_tabs()
+            .iter()
+            .map(|tab| tab.name.clone())
+            .collect();
+
+        let Some(first) = targets.first() else {
+            return false;
+        };
+
+        let label = labels::Label::next(self.labels.get(first).copied());
+        for name in targets {
+            match label {
+                Some(label) => self.labels.insert(name, label),
+                None => self.labels.remove(&name),
+            };
+        }
+
+        self.invalidate_rows();
+        storage::save(labels::FILE, &self.labels);
+        true
+    }
+
+    fn save_tags(&mut self, input: &str, targets: Vec<String>) {
+        self.invalidate_rows();
+
+        let tags: Vec<String> = input.split_whitespace().map(String::from).collect();
+
+        self.history.record(Change::Tags(
+            targets
+                .iter()
+                .map(|name| {
+                    (
+                        name.clone(),
+                        self.tags.get(name).cloned().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        ));
+
+        for name in targets {
+            if tags.is_empty() {
+                self.tags.remove(&name);
+            } else {
+                self.tags.insert(name, tags.clone());
+            }
+        }
+
+        storage::save(tags::FILE, &self.tags);
+    }
+
+    fn render_group(&self, group: &str) -> String {
+        let row = format!(
+            "{} {} ({})",
+            if self.collapsed.contains(group) {
+                "▸"
+            } else {
+                "▾"
+            },
+            group,
+            self.group_size(group)
+        )
+        .bold()
+        .to_string();
+
+        if self.highlighted == Some(Row::Group(group.to_string())) {
+            self.highlight(row)
+        } else {
+            row
+        }
+    }
+
+    /// Renders the whole frame into one buffer, printed at once so the host never shows half of
+    /// it.
+    fn render_frame(&mut self, frame: &mut String, rows: usize, cols: usize) -> fmt::Result {
+        // blank every line of the pane first, so a frame shorter or narrower than the previous
+        // one (e.g. after typing a filter) doesn't leave parts of it behind
+        for line in 1..=rows {
+            write!(frame, "\u{1b}[{line};1H\u{1b}[K")?;
+        }
+        write!(frame, "\u{1b}[H")?;
+        self.cols = cols;
+        self.rows = rows;
+
+        if !self.tabs_known {
+            return writeln!(frame, "{}", "loading tabs…".dimmed().italic());
+        }
+
+        if let Some(session_name) = self.session_name.as_ref().filter(|_| self.show_header) {
+            let panes: usize = self
+                .tabs
+                .iter()
+                .map(|tab| self.pane_count(tab.position))
+                .sum();
+            let hidden = self.tabs.len() - self.viewable_tabs_iter().count();
+
+            let header = format!(
+                "{} {}{}{}",
+                session_name.bold(),
+                format!(
+                    "· {} · {} · {}",
+                    plural(self.connected_clients, "client"),
+                    plural(self.tabs.len(), "tab"),
+                    plural(panes, "pane")
+                )
+                .dimmed(),
+                if hidden == 0 {
+                    String::new()
+                } else {
+                    format!(" ({hidden} hidden)").dimmed().italic().to_string()
+                },
+                if self.marked.is_empty() {
+                    String::new()
+                } else {
+                    format!(" · {} marked", self.marked.len())
+                        .yellow()
+                        .to_string()
+                }
+            );
+
+            writeln!(frame, "{}", width::truncate(&header, cols))?;
+        }
+
+        if !self.mode.handler().render(self, frame)? {
+            return Ok(());
+        }
 
-impl State {
-    fn filter(&self, tab: &&TabInfo) -> bool {
-        if self.ignore_case {
-            tab.name.to_lowercase() == self.filter.to_lowercase()
-                || tab
-                    .name
-                    .to_lowercase()
-                    .contains(&self.filter.to_lowercase())
+        // every tab closed while room was open, or a session that has none yet
+        if self.tabs.is_empty() {
+            writeln!(
+                frame,
+                "{}\n{} to open a new tab",
+                "no tabs".dimmed().italic(),
+                "T".cyan().bold()
+            )?;
+
+            return self.render_status(frame);
+        }
+
+        let list = self.rows();
+        let height = self.list_height(rows);
+        self.scroll_to_selection(&list, height);
+        let visible = self.rows_fitting(&list, self.scroll, height);
+        self.page_size = visible;
+
+        let selected_row = self.selected_row();
+        let visual: Vec<usize> = if matches!(self.mode, Mode::Visual(_)) {
+            self.visual_range().iter().map(|tab| tab.position).collect()
         } else {
-            tab.name == self.filter || tab.name.contains(&self.filter)
+            Vec::new()
+        };
+
+        for row in list.iter().skip(self.scroll).take(visible) {
+            let rendered = match row {
+                Row::Group(group) => Some(self.render_group(group)),
+                Row::Session(session_name) => Some(self.render_session(session_name)),
+                Row::SessionTab(session_name, position) => self
+                    .other_sessions()
+                    .find(|session| &session.name == session_name)
+                    .and_then(|session| session.tabs.iter().find(|tab| tab.position == *position))
+                    .map(|tab| self.render_session_tab(session_name, tab)),
+                Row::Tab(position) => self
+                    .tabs
+                    .iter()
+                    .find(|tab| tab.position == *position)
+                    .map(|tab| self.render_tab(tab)),
+            };
+
+            let Some(rendered) = rendered else {
+                continue;
+            };
+
+            let selected = selected_row.as_ref() == Some(row);
+            let in_visual = matches!(row, Row::Tab(position) if visual.contains(position));
+            let rendered = if !self.color {
+                let marker = if selected {
+                    ">"
+                } else if in_visual {
+                    "+"
+                } else {
+                    " "
+                };
+
+                format!("{marker} {rendered}")
+            } else if selected {
+                rendered
+            } else if in_visual {
+                rendered.on_blue().to_string()
+            } else if self.hovered.as_ref() == Some(row) {
+                rendered.underline().to_string()
+            } else {
+                rendered
+            };
+
+            // rows are cut short of the scrollbar, so none of them wraps onto a second line
+            writeln!(
+                frame,
+                "{}",
+                width::truncate(&rendered, cols.saturating_sub(1))
+            )?;
+
+            if let Row::Tab(position) = row {
+                if self.show_pane_titles {
+                    writeln!(
+                        frame,
+                        "{}",
+                        self.pane_titles(*position, cols.saturating_sub(1)).dimmed()
+                    )?;
+                }
+            }
+        }
+
+        if let Some(breadcrumb) = self.breadcrumb() {
+            writeln!(
+                frame,
+                "{}",
+                width::truncate(&breadcrumb, cols.saturating_sub(1))
+            )?;
+        }
+
+        if self.show_footer {
+            writeln!(
+                frame,
+                "{}",
+                width::truncate(&self.footer(), cols.saturating_sub(1))
+            )?;
+        }
+
+        self.render_status(frame)?;
+
+        if self.preview {
+            writeln!(frame, "{}", "─".repeat(cols).dimmed())?;
+
+            if let Some(contents) = &self.preview_contents {
+                for line in &contents.lines {
+                    writeln!(frame, "{}", width::truncate(line, cols).dimmed())?;
+                }
+            }
         }
+
+        self.render_scrollbar(frame, list.len(), visible, height, cols)
     }
 
-    fn viewable_tabs_iter(&self) -> impl Iterator<Item = &TabInfo> {
-        self.tabs.iter().filter(|tab| self.filter(tab))
+    /// Writes one line per choice of a picker, highlighting the selected one.
+    fn render_choices(
+        &self,
+        frame: &mut String,
+        choices: impl Iterator<Item = String>,
+        selected: Option<usize>,
+    ) -> fmt::Result {
+        for (index, choice) in choices.enumerate() {
+            match (self.color, Some(index) == selected) {
+                (true, true) => writeln!(frame, "{}", self.theme.selected.style(choice))?,
+                (true, false) => writeln!(frame, "{choice}")?,
+                (false, true) => writeln!(frame, "> {choice}")?,
+                (false, false) => writeln!(frame, "  {choice}")?,
+            }
+        }
+
+        Ok(())
     }
 
-    fn viewable_tabs(&self) -> Vec<&TabInfo> {
-        self.viewable_tabs_iter().collect()
+    /// Highlights the selected row. Without colors the list marks it with `>` instead.
+    fn highlight(&self, row: String) -> String {
+        if self.color {
+            self.theme.selected.style(row).to_string()
+        } else {
+            row
+        }
     }
 
-    fn reset_selection(&mut self) {
-        let tabs = self.viewable_tabs();
+    /// room's version with the sort order and what the filter searches, so the settings toggled
+    /// while room runs are always on screen.
+    fn footer(&self) -> String {
+        format!(
+            "room {} · sort: {}{} · scope: {}",
+            env!("CARGO_PKG_VERSION"),
+            self.sort_order,
+            if self.sort_reverse { ", reversed" } else { "" },
+            if self.show_other_sessions {
+                "tabs, sessions"
+            } else {
+                "tabs"
+            }
+        )
+        .dimmed()
+        .to_string()
+    }
 
-        if tabs.is_empty() {
-            self.selected = None
-        } else if let Some(tab) = tabs.first() {
-            self.selected = Some(tab.position)
+    /// The last few tabs of the session's trail, ending with where `[` goes back from.
+    fn breadcrumb(&self) -> Option<String> {
+        let trail = self.trails.get(self.session_name.as_ref()?)?;
+        let (current, before) = trail.breadcrumb(BREADCRUMB_LENGTH).split_last()?;
+
+        if before.is_empty() {
+            return None;
         }
+
+        let before: Vec<String> = before
+            .iter()
+            .map(|name| name.dimmed().to_string())
+            .collect();
+
+        Some(format!(
+            "{} {}",
+            before.join(&" › ".dimmed().to_string()),
+            format!("› {current}").bold()
+        ))
     }
 
-    fn select_down(&mut self) {
-        let tabs = self.tabs.iter().filter(|tab| self.filter(tab));
+    fn render_status(&self, frame: &mut String) -> fmt::Result {
+        if let Some(status) = &self.status {
+            let status = status.yellow().to_string();
 
-        let mut can_select = false;
-        let mut first = None;
-        for TabInfo { position, .. } in tabs {
-            if first.is_none() {
-                first.replace(position);
-            }
+            writeln!(frame, "{}", width::truncate(&status, self.cols))?;
+        }
 
-            if can_select {
-                self.selected = Some(*position);
-                return;
-            } else if Some(*position) == self.selected {
-                can_select = true;
-            }
+        Ok(())
+    }
+
+    fn render_session(&self, session_name: &str) -> String {
+        let row = format!("── {session_name} ──").bold().to_string();
+
+        if self.highlighted == Some(Row::Session(session_name.to_string())) {
+            self.highlight(row)
+        } else {
+            row
         }
+    }
 
-        if let Some(position) = first {
-            self.selected = Some(*position)
+    fn render_session_tab(&self, session_name: &str, tab: &TabInfo) -> String {
+        let row = format!("  {} - {}", tab.position + 1, tab.name);
+
+        if self.highlighted == Some(Row::SessionTab(session_name.to_string(), tab.position)) {
+            self.highlight(row)
+        } else {
+            row.dimmed().to_string()
         }
     }
 
-    fn select_up(&mut self) {
-        let tabs = self.tabs.iter().filter(|tab| self.filter(tab)).rev();
+    fn render_tab(&self, tab: &TabInfo) -> String {
+        let indent = if self.group_of(&tab.name).is_some() {
+            "  "
+        } else {
+            ""
+        };
+
+        let icon = self
+            .panes
+            .get(&tab.position)
+            .and_then(|panes| self.icons.for_panes(panes))
+            .map(|icon| format!("{icon} "))
+            .unwrap_or_default();
+        let label = format!("{} - {icon}{}", tab.position + 1, tab.name);
+        let label = match (self.color, tab.active) {
+            (true, _) => label,
+            (false, true) => format!("* {label}"),
+            (false, false) => format!("  {label}"),
+        };
+
+        let row = if tab.active {
+            self.theme.active_tab.style(label).to_string()
+        } else if !self.has_terminals(tab.position) {
+            self.theme.empty_tab.style(label).to_string()
+        } else if self.is_stale(&tab.name) {
+            self.theme.stale_tab.style(label).to_string()
+        } else {
+            label
+        };
+
+        let row = if Some(tab.position) == self.selected {
+            self.highlight(row)
+        } else {
+            row
+        };
+
+        let row = match self.labels.get(&tab.name) {
+            Some(label) => format!("{} {row}", label.chip()),
+            None if self
+                .tabs
+                .iter()
+                .any(|tab| self.labels.contains_key(&tab.name)) =>
+            {
+                format!("  {row}")
+            }
+            None => row,
+        };
 
-        let mut can_select = false;
-        let mut last = None;
-        for TabInfo { position, .. } in tabs {
-            if last.is_none() {
-                last.replace(position);
+        let row = if self.marked.contains(&tab.tab_id) {
+            format!("{} {row}", "●".yellow())
+        } else if !self.marked.is_empty() {
+            format!("  {row}")
+        } else {
+            row
+        };
+
+        let row = if self.show_pane_counts {
+            let floating = self.floating_count(tab.position);
+            let tiled = self.pane_count(tab.position) - floating;
+            let count = if floating == 0 {
+                format!("[{tiled}]")
+            } else {
+                format!("[{tiled}+{floating} floating]")
+            };
+
+            format!("{row} {}", count.dimmed())
+        } else {
+            row
+        };
+
+        // other clients attached to the session that are looking at this tab
+        let row = match tab.other_focused_clients.len() {
+            0 => row,
+            others => format!(
+                "{row} {}",
+                format!("[{} here]", plural(others, "client")).cyan()
+            ),
+        };
+
+        let row = match self.focus_age(&tab.name) {
+            Some(age) if self.show_focus_age && !tab.active => {
+                format!("{row} {}", format!("{} ago", duration(age)).dimmed())
             }
+            _ => row,
+        };
+
+        let row = match self.tags.get(&tab.name) {
+            Some(tags) => tags.iter().fold(format!("{indent}{row}"), |row, tag| {
+                format!("{row} {}", tags::chip(tag))
+            }),
+            None => format!("{indent}{row}"),
+        };
+
+        match self.tab_note(&tab.name) {
+            Some(note) => format!("{row} {}", note.dimmed().italic()),
+            None => row,
+        }
+    }
 
-            if can_select {
-                self.selected = Some(*position);
+    /// Asks the match worker to match other sessions' tabs against the filter. `force` asks again
+    /// for the same filter, after the sessions changed.
+    fn request_session_matches(&mut self, force: bool) {
+        if !self.show_other_sessions || self.filter.is_empty() {
+            self.match_query = None;
+            return;
+        }
+
+        let query = (self.filter.clone(), self.match_options());
+        if !force && self.match_query.as_ref() == Some(&query) {
+            return;
+        }
+
+        self.match_generation += 1;
+
+        let request = MatchRequest {
+            generation: self.match_generation,
+            filter: query.0.clone(),
+            options: query.1,
+            candidates: self
+                .other_sessions()
+                .flat_map(|session| {
+                    session.tabs.iter().map(|tab| Candidate {
+                        session_name: session.name.clone(),
+                        position: tab.position,
+                        name: tab.name.clone(),
+                    })
+                })
+                .collect(),
+            tags: self.tags.clone(),
+            labels: self.labels.clone(),
+        };
+        self.match_query = Some(query);
+
+        if let Ok(payload) = serde_json::to_string(&request) {
+            self.host
+                .post_to_worker(matcher::WORKER_NAME, matcher::REQUEST, &payload);
+        }
+    }
+
+    /// Asks the preview worker for the selected tab's focused terminal when it's changed, or
+    /// again for the same one on `refresh` so the preview follows what it prints.
+    fn request_preview(&mut self, refresh: bool) {
+        let target = self.selected.and_then(|position| {
+            self.focused_terminal(position)
+                .map(|pane_id| (position, pane_id))
+        });
+
+        if target == self.preview_target {
+            if !refresh {
                 return;
-            } else if Some(*position) == self.selected {
-                can_select = true;
             }
+        } else {
+            self.preview_target = target;
+            self.preview_contents = None;
         }
 
-        if let Some(position) = last {
-            self.selected = Some(*position)
+        if let Some((tab_position, pane_id)) = target {
+            let request = PreviewRequest {
+                tab_position,
+                pane_id,
+                lines: self.preview_lines,
+            };
+
+            if let Ok(payload) = serde_json::to_string(&request) {
+                self.host
+                    .post_to_worker(preview::WORKER_NAME, preview::REQUEST, &payload);
+            }
         }
     }
 }
 
+/// A short, rounded down duration such as `45s`, `2m`, `3h` or `5d`.
+fn duration(seconds: i64) -> String {
+    match seconds {
+        ..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// Where `path`, relative to the folder Zellij was started in, is under `/host`. Absolute paths
+/// and `..` are refused, since they'd reach files outside that folder.
+fn host_path(path: &str) -> Result<std::path::PathBuf, RoomError> {
+    let relative = std::path::Path::new(path);
+
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|component| component == std::path::Component::ParentDir)
+    {
+        return Err(RoomError::Input(format!(
+            "{path} is outside the folder Zellij was started in"
+        )));
+    }
+
+    Ok(std::path::Path::new("/host").join(relative))
+}
+
+/// Parses durations like `90`, `45s`, `30m`, `12h` or `7d` into seconds.
+fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+
+    number.parse::<i64>().ok()?.checked_mul(multiplier)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
 register_plugin!(State);
+register_worker!(PreviewWorker, preview_worker, PREVIEW_WORKER);
+register_worker!(MatchWorker, matcher_worker, MATCHER_WORKER);
 
 impl ZellijPlugin for State {
     fn load(&mut self, configuration: BTreeMap<String, String>) {
         // we need the ReadApplicationState permission to receive the ModeUpdate and TabUpdate
         // events
         // we need the ChangeApplicationState permission to Change Zellij state (Panes, Tabs and UI)
+        // we need the ReadPaneContents permission to preview the selected tab's focused pane
+        // we need the RunCommands and OpenTerminalsOrPlugins permissions to open command panes
+        // we need the ReadCliPipes permission to receive `zellij pipe` messages
         request_permission(&[
             PermissionType::ReadApplicationState,
             PermissionType::ChangeApplicationState,
+            PermissionType::ReadPaneContents,
+            PermissionType::RunCommands,
+            PermissionType::OpenTerminalsOrPlugins,
+            PermissionType::ReadCliPipes,
         ]);
 
-        self.ignore_case = match configuration.get("ignore_case" as &str) {
-            Some(value) => value.trim().parse().unwrap(),
-            None => true,
+        // first, so that invalid values in the rest of the configuration are logged
+        self.debug = self.config(&configuration, "debug", false);
+
+        self.initial_cwd = get_plugin_ids().initial_cwd;
+        self.visible = true;
+        self.resident = self.config(&configuration, "resident", false);
+        self.esc_behavior = self.config(&configuration, "esc_behavior", EscBehavior::default());
+
+        self.ignore_case = self.config(&configuration, "ignore_case", true);
+        self.ignore_accents = self.config(&configuration, "ignore_accents", false);
+
+        // https://no-color.org, when Zellij passes the variable on to plugins
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        self.color = self.config(&configuration, "color", !no_color);
+
+        self.fuzzy = self.config(&configuration, "fuzzy", false);
+
+        self.search_enter_focuses = self.config(&configuration, "search_enter_focuses", false);
+
+        self.export_format = self.config(&configuration, "export_format", export::Format::Text);
+
+        self.layout_dump_path = match configuration.get("layout_dump_path" as &str) {
+            Some(value) => value.trim().to_string(),
+            None => "session-layout.kdl".to_string(),
+        };
+
+        self.floating_command = configuration
+            .get("floating_command" as &str)
+            .filter(|value| !value.trim().is_empty())
+            .cloned();
+
+        self.scratch_dir = match configuration.get("scratch_dir" as &str) {
+            Some(value) => value.trim().to_string(),
+            None => "~/.local/share/room/scratch".to_string(),
+        };
+
+        let renumber_format = configuration
+            .get("renumber_format" as &str)
+            .map_or("{n}. {name}", String::as_str);
+        self.numbering = Numbering::new(renumber_format).ok();
+        if self.numbering.is_none() {
+            self.report_error(RoomError::Config {
+                key: "renumber_format".to_string(),
+                value: renumber_format.to_string(),
+            });
+        }
+
+        self.hide_plugin_tabs = self.config(&configuration, "hide_plugin_tabs", false);
+
+        self.show_other_sessions = self.config(&configuration, "other_sessions", false);
+
+        self.name_launched_tabs = self.config(&configuration, "name_launched_tabs", true);
+
+        self.configured_layouts = match configuration.get("layouts" as &str) {
+            Some(value) => value.split_whitespace().map(String::from).collect(),
+            None => Vec::new(),
+        };
+
+        self.new_tab_layout = configuration
+            .get("new_tab_layout" as &str)
+            .filter(|value| !value.is_empty())
+            .cloned();
+
+        self.group_separator = configuration
+            .get("group_separator" as &str)
+            .filter(|value| !value.is_empty())
+            .cloned();
+
+        self.actions = configuration
+            .iter()
+            .filter_map(|(key, value)| {
+                let mut chars = key.strip_prefix("action_")?.chars();
+                let (Some(c), None) = (chars.next(), chars.next()) else {
+                    return None;
+                };
+
+                Some((c, value.clone()))
+            })
+            .collect();
+
+        self.custom_actions = BTreeMap::new();
+        for (key, value) in &configuration {
+            let mut chars = key
+                .strip_prefix("custom_action_")
+                .unwrap_or_default()
+                .chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                continue;
+            };
+
+            match Step::parse_script(value) {
+                Some(steps) => {
+                    self.custom_actions.insert(c, steps);
+                }
+                None => self.report_error(RoomError::Config {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+            }
+        }
+
+        self.bindings = Vec::new();
+        for (key, value) in &configuration {
+            let Some(bound) = key.strip_prefix("bind_") else {
+                continue;
+            };
+
+            match (bound.parse::<KeyWithModifier>(), Action::parse(value)) {
+                (Ok(bound), Some(action)) => self.bindings.push((bound, action)),
+                _ => self.report_error(RoomError::Config {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+            }
+        }
+
+        self.presets = BTreeMap::new();
+        for (key, value) in &configuration {
+            let Some(number) = key.strip_prefix("preset_") else {
+                continue;
+            };
+
+            // presets are applied with F1 to F12
+            match number.parse() {
+                Ok(number @ 1..=12) => {
+                    self.presets.insert(number, value.clone());
+                }
+                _ => self.report_error(RoomError::Config {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+            }
+        }
+
+        self.templates =
+            Template::from_configuration(&configuration, |error| self.report_error(error));
+        let theme = configuration
+            .get("theme" as &str)
+            .map_or("default", |name| name.trim());
+        let preset = Theme::preset(theme).unwrap_or_else(|| {
+            self.report_error(RoomError::Config {
+                key: "theme".to_string(),
+                value: theme.to_string(),
+            });
+
+            Theme::default()
+        });
+        self.theme = preset.with_configuration(&configuration, |error| self.report_error(error));
+        let nerd_fonts = self.config(&configuration, "nerd_fonts", true);
+        self.icons = Icons::from_configuration(&configuration, nerd_fonts);
+        self.tags = storage::load(tags::FILE);
+        self.labels = storage::load(labels::FILE);
+        self.notes = storage::load(NOTES_FILE);
+        self.trails = storage::load(trail::FILE);
+        self.workspaces = storage::load(workspaces::FILE);
+        self.recent = storage::load(RECENT_FILE);
+        self.searches = storage::load(SEARCHES_FILE);
+
+        self.restore_selection = self.config(&configuration, "restore_selection", false);
+        if self.restore_selection {
+            self.last_selected = storage::load(SELECTION_FILE);
+        } else {
+            self.restored = true;
+        }
+        self.focused = storage::load(FOCUSED_FILE);
+
+        self.stale_after = match configuration.get("stale_after" as &str) {
+            Some(value) => parse_duration(value).unwrap_or_else(|| {
+                self.report_error(RoomError::Config {
+                    key: "stale_after".to_string(),
+                    value: value.clone(),
+                });
+
+                86400
+            }),
+            None => 86400,
         };
 
-        subscribe(&[EventType::TabUpdate, EventType::Key]);
+        self.show_pane_titles = self.config(&configuration, "show_pane_titles", false);
+
+        self.show_pane_counts = self.config(&configuration, "show_pane_counts", false);
+        self.show_header = self.config(&configuration, "show_header", true);
+        self.show_footer = self.config(&configuration, "show_footer", false);
+        self.show_mode_line = self.config(&configuration, "show_mode_line", true);
+
+        self.show_focus_age = self.config(&configuration, "show_focus_age", false);
+
+        self.sort_order = self.config(&configuration, "sort", SortOrder::Position);
+
+        self.sort_reverse = self.config(&configuration, "sort_reverse", false);
+        self.pin_active_tab = self.config(&configuration, "pin_active_tab", ActivePin::None);
+
+        self.preview = self.config(&configuration, "preview", false);
+
+        self.preview_lines = self.config(&configuration, "preview_lines", 10);
+
+        if let Some(mode) = configuration.get("mode" as &str) {
+            match mode.trim() {
+                "normal" => self.mode = Mode::Normal(Normal),
+                "search" => self.mode = Mode::Search(Search::new(&self.filter)),
+                "sessions" => self.mode = Mode::Sessions(Sessions::default()),
+                _ => self.report_error(RoomError::Config {
+                    key: "mode".to_string(),
+                    value: mode.clone(),
+                }),
+            }
+        }
+
+        if let Some(action) = configuration.get("action" as &str) {
+            match action.trim().parse::<LaunchAction>() {
+                Ok(action) => {
+                    self.silent = action.is_silent();
+                    self.launch_action = Some(action);
+                }
+                Err(()) => self.report_error(RoomError::Config {
+                    key: "action".to_string(),
+                    value: action.clone(),
+                }),
+            }
+        }
+
+        subscribe(&[
+            EventType::TabUpdate,
+            EventType::PaneUpdate,
+            EventType::SessionUpdate,
+            EventType::Timer,
+            EventType::CustomMessage,
+            EventType::Key,
+            EventType::Mouse,
+            EventType::Visible,
+        ]);
     }
 
     fn update(&mut self, event: Event) -> bool {
+        self.log(format_args!("event {event:?}"));
+
+        let before = (self.mode.clone(), self.selected, self.highlighted.clone());
+        // matches found for other sessions go stale while hidden, so showing room redoes them
+        let sessions_changed = matches!(event, Event::SessionUpdate(..) | Event::Visible(true));
+        let refresh_preview = matches!(event, Event::PaneUpdate(_) | Event::Timer(_));
+        if !matches!(event, Event::Key(_)) {
+            self.invalidate_rows();
+        }
+
         let mut should_render = false;
         match event {
             Event::TabUpdate(tab_info) => {
-                self.selected =
-                    tab_info.iter().find_map(
-                        |tab| {
-                            if tab.active {
-                                Some(tab.position)
-                            } else {
-                                None
-                            }
-                        },
-                    );
+                let previous_index = self
+                    .viewable_tabs()
+                    .iter()
+                    .position(|tab| Some(tab.position) == self.selected);
+                self.pending.retain(|action| !action.is_settled(&tab_info));
+
+                // positions shift as tabs open, close and move, so follow the selected tab by id;
+                // a hidden room has nothing on screen to keep, so it follows the active tab
+                let selected_id = if self.visible {
+                    self.selected_tab().map(|tab| tab.tab_id)
+                } else {
+                    None
+                };
+                let follow = if self.pending.is_empty() {
+                    self.highlighted = None;
+                    self.pending_selection.take().or(selected_id)
+                } else {
+                    selected_id
+                };
+                let active = tab_info
+                    .iter()
+                    .find(|tab| tab.active)
+                    .map(|tab| tab.position);
+
+                self.selected = match follow {
+                    Some(tab_id) => tab_info
+                        .iter()
+                        .find(|tab| tab.tab_id == tab_id)
+                        .map(|tab| tab.position),
+                    None => active,
+                };
 
                 self.tabs = tab_info;
+                self.tabs_known = true;
+                self.repair_selection(previous_index);
+                if self.selected.is_none() {
+                    self.selected = active;
+                }
+                self.restore_selection();
+                self.marked
+                    .retain(|tab_id| self.tabs.iter().any(|tab| tab.tab_id == *tab_id));
+                self.record_active_tab();
+                if let Some(action) = self.launch_action.take() {
+                    self.run_launch_action(action);
+                }
                 should_render = true;
             }
+            Event::SessionUpdate(sessions, _) => {
+                if let Some(session) = sessions.iter().find(|s| s.is_current_session).cloned() {
+                    self.layouts = session.available_layouts;
 
-            Event::Key(Key::Esc | Key::Ctrl('c')) => {
-                close_focus();
-            }
+                    should_render = matches!(self.mode, Mode::Layouts(_) | Mode::Sessions(_))
+                        || self.show_other_sessions
+                        || self.session_name.as_ref() != Some(&session.name)
+                        || self.connected_clients != session.connected_clients;
 
-            Event::Key(Key::Down | Key::BackTab) => {
-                self.select_down();
+                    self.connected_clients = session.connected_clients;
+                    self.session_name = Some(session.name);
+                    self.record_active_tab();
+                    self.restore_selection();
+                }
 
-                should_render = true;
+                self.sessions = sessions;
+                let names = modes::session_names(self);
+                if let Mode::Sessions(picker) = &mut self.mode {
+                    picker.list.retain(&names);
+                }
             }
-            Event::Key(Key::Up | Key::Ctrl('k')) => {
-                self.select_up();
-
-                should_render = true;
+            Event::PaneUpdate(pane_manifest) => {
+                self.panes = pane_manifest.panes;
             }
-            Event::Key(Key::Char('\n')) => {
-                let tab = self
-                    .tabs
-                    .iter()
-                    .find(|tab| Some(tab.position) == self.selected);
+            Event::Timer(_) => {
+                self.preview_timer = false;
+            }
+            Event::Visible(visible) => {
+                self.visible = visible;
+                should_render = visible;
 
-                if let Some(tab) = tab {
-                    close_focus();
-                    switch_tab_to(tab.position as u32 + 1);
+                // a resident room opens fresh, with its tabs, history and marks kept
+                if !visible && self.resident {
+                    self.mode = Mode::Normal(Normal);
+                    self.status = None;
+                    self.silent = false;
                 }
             }
-            Event::Key(Key::Backspace) => {
-                self.filter.pop();
-
-                self.reset_selection();
-
-                should_render = true;
+            Event::CustomMessage(message, payload) if message == matcher::RESPONSE => {
+                match serde_json::from_str::<MatchResponse>(&payload) {
+                    Ok(response) if response.generation == self.match_generation => {
+                        self.session_matches = response.matches;
+                        should_render = true;
+                    }
+                    Ok(_) => (),
+                    Err(error) => self.log(format_args!("malformed match response: {error}")),
+                }
+            }
+            Event::CustomMessage(message, payload) if message == preview::RESPONSE => {
+                match serde_json::from_str::<PreviewResponse>(&payload) {
+                    Ok(contents) if self.selected == Some(contents.tab_position) => {
+                        self.preview_contents = Some(contents);
+                        should_render = true;
+                    }
+                    Ok(_) => (),
+                    Err(error) => self.log(format_args!("malformed preview response: {error}")),
+                }
             }
-            Event::Key(Key::Char(c)) if c.is_ascii_alphabetic() || c.is_ascii_digit() => {
-                self.filter.push(c);
 
-                self.reset_selection();
+            Event::Mouse(Mouse::Hover(line, _)) => {
+                let hovered = self.row_at(line);
 
-                should_render = true;
+                should_render = hovered != self.hovered;
+                self.hovered = hovered;
+            }
+            Event::Mouse(Mouse::LeftClick(line, _)) => {
+                if let Some(row) = self.row_at(line) {
+                    self.click_row(row);
+                    should_render = true;
+                }
+            }
+            Event::Key(key) if key.is_key_with_ctrl_modifier(BareKey::Char('c')) => {
+                self.dismiss();
+            }
+            Event::Key(key) => {
+                should_render = self.status.take().is_some();
+                should_render |= self.handle_key(key);
             }
             _ => (),
         };
 
+        // hidden, room keeps following tabs and panes (so recent tabs and focus times are current
+        // when it's shown) but leaves the workers and drawing alone
+        if !self.visible {
+            return false;
+        }
+
+        if self.preview {
+            self.request_preview(refresh_preview);
+
+            if !self.preview_timer {
+                self.host.set_timeout(preview::REFRESH_SECONDS);
+                self.preview_timer = true;
+            }
+        }
+
+        self.request_session_matches(sessions_changed);
+
+        if self.restore_selection && self.restored && before.1 != self.selected {
+            self.save_selection();
+        }
+
+        if self.debug && before != (self.mode.clone(), self.selected, self.highlighted.clone()) {
+            self.log(format_args!(
+                "state mode {:?}, selected {:?}, highlighted {:?}",
+                self.mode, self.selected, self.highlighted
+            ));
+        }
+
         should_render
     }
 
-    fn render(&mut self, _rows: usize, _cols: usize) {
-        println!(
-            "{} {}",
-            ">".cyan().bold(),
-            if self.filter.is_empty() {
-                "(filter)".dimmed().italic().to_string()
-            } else {
-                self.filter.dimmed().italic().to_string()
+    fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        self.log(format_args!("pipe {pipe_message:?}"));
+
+        match pipe_message.name.as_str() {
+            "toggle" => {
+                if self.visible && self.rendered {
+                    self.host.hide_plugin();
+                } else {
+                    self.host.show_plugin();
+                }
+
+                false
             }
-        );
+            "action" => {
+                let name = pipe_message.payload.unwrap_or_default();
 
-        println!(
-            "{}",
-            self.viewable_tabs_iter()
-                .map(|tab| {
-                    let row = if tab.active {
-                        format!("{} - {}", tab.position + 1, tab.name)
-                            .red()
-                            .bold()
-                            .to_string()
-                    } else {
-                        format!("{} - {}", tab.position + 1, tab.name)
-                    };
-
-                    if Some(tab.position) == self.selected {
-                        row.on_cyan().to_string()
-                    } else {
-                        row
+                match Action::parse(&name) {
+                    Some(action) => self.dispatch(action),
+                    None => {
+                        self.report_error(RoomError::Input(format!("unknown action {name:?}")));
+                        true
                     }
-                })
-                .collect::<Vec<String>>()
-                .join("\n")
-        );
+                }
+            }
+            // answers `zellij pipe --name dump` on its stdout, for scripts
+            "dump" => {
+                if let PipeSource::Cli(pipe_id) = &pipe_message.source {
+                    let dump = export::to_json(&self.tab_summaries());
+
+                    cli_pipe_output(pipe_id, &format!("{dump}\n"));
+                    unblock_cli_pipe_input(pipe_id);
+                }
+
+                false
+            }
+            "export_data" | "import_data" => {
+                let path = pipe_message
+                    .payload
+                    .filter(|path| !path.trim().is_empty())
+                    .unwrap_or_else(|| backup::DEFAULT_PATH.to_string());
+
+                if pipe_message.name == "export_data" {
+                    self.export_data(path.trim());
+                } else {
+                    self.import_data(path.trim());
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&mut self, rows: usize, cols: usize) {
+        self.rendered = true;
+
+        if self.silent {
+            return;
+        }
+
+        let mut frame = String::with_capacity(rows * cols);
+
+        if self.render_frame(&mut frame, rows, cols).is_err() {
+            return;
+        }
+
+        if self.color {
+            print!("{frame}");
+        } else {
+            print!("{}", width::strip_colors(&frame));
+        }
     }
 }
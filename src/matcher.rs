@@ -0,0 +1,219 @@
+use crate::labels::Label;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use zellij_tile::prelude::*;
+
+pub const WORKER_NAME: &str = "matcher";
+pub const REQUEST: &str = "match_tabs";
+pub const RESPONSE: &str = "tab_matches";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Options {
+    pub ignore_case: bool,
+    pub fuzzy: bool,
+    pub ignore_accents: bool,
+}
+
+/// Scores how well a filter term matches, higher is better. Like in fzf, `^needle` anchors it to
+/// the start and `needle$` to the end. Exact matches score by how early they start; fuzzy ones
+/// (the needle's characters in order) lose points for every character skipped in between.
+pub fn score(haystack: &str, needle: &str, options: Options) -> Option<i64> {
+    let (needle, anchor_start) = match needle.strip_prefix('^') {
+        Some(needle) => (needle, true),
+        None => (needle, false),
+    };
+    let (needle, anchor_end) = match needle.strip_suffix('$') {
+        Some(needle) => (needle, true),
+        None => (needle, false),
+    };
+
+    // a lone anchor is how typing one starts, so it shouldn't blank the list before the rest
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    // lowercasing ascii is cheaper and gives the same result, so tab names that are all ascii
+    // (the usual case) skip the unicode tables
+    let (haystack, needle) = if options.ignore_case && haystack.is_ascii() && needle.is_ascii() {
+        (haystack.to_ascii_lowercase(), needle.to_ascii_lowercase())
+    } else if options.ignore_case {
+        (haystack.to_lowercase(), needle.to_lowercase())
+    } else {
+        (haystack.to_string(), needle.to_string())
+    };
+
+    let (haystack, needle) = if options.ignore_accents {
+        (strip_accents(&haystack), strip_accents(&needle))
+    } else {
+        (haystack, needle)
+    };
+
+    if options.fuzzy {
+        return fuzzy_score(&haystack, &needle, anchor_start, anchor_end);
+    }
+
+    let start = match (anchor_start, anchor_end) {
+        (true, true) => (haystack == needle).then_some(0),
+        (true, false) => haystack.starts_with(&needle).then_some(0),
+        (false, true) => haystack
+            .ends_with(&needle)
+            .then(|| haystack.len() - needle.len()),
+        (false, false) => haystack.find(&needle),
+    }?;
+
+    Some(-(start as i64))
+}
+
+/// Decomposes the text and drops the combining marks, so `dépôt` becomes `depot`.
+fn strip_accents(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+fn fuzzy_score(haystack: &str, needle: &str, anchor_start: bool, anchor_end: bool) -> Option<i64> {
+    if anchor_start && !haystack.starts_with(needle.chars().next().unwrap_or_default()) {
+        return None;
+    }
+
+    if anchor_end && !haystack.ends_with(needle.chars().last().unwrap_or_default()) {
+        return None;
+    }
+
+    let mut skipped = 0;
+    let mut haystack = haystack.chars();
+
+    for c in needle.chars() {
+        loop {
+            if haystack.next()? == c {
+                break;
+            }
+
+            skipped += 1;
+        }
+    }
+
+    Some(-skipped)
+}
+
+/// A tab of another session, for the worker to match.
+#[derive(Serialize, Deserialize)]
+pub struct Candidate {
+    pub session_name: String,
+    pub position: usize,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MatchRequest {
+    pub generation: u64,
+    pub filter: String,
+    pub options: Options,
+    pub candidates: Vec<Candidate>,
+    pub tags: BTreeMap<String, Vec<String>>,
+    pub labels: BTreeMap<String, Label>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MatchResponse {
+    pub generation: u64,
+    /// Session names and tab positions of the matching tabs, best first.
+    pub matches: Vec<(String, usize)>,
+}
+
+/// Matches other sessions' tabs against the filter off the main thread, so typing never waits on
+/// scoring hundreds of tabs. Only names, tags and labels are known for those tabs, so `cmd:` and
+/// `:stale` terms never match them. The current session's tabs are still scored in
+/// `State::filter`: the list and selection follow every key, which a round trip to the worker
+/// would lag behind.
+#[derive(Default, Serialize, Deserialize)]
+pub struct MatchWorker {}
+
+impl MatchWorker {
+    fn score(request: &MatchRequest, candidate: &Candidate) -> Option<i64> {
+        request
+            .filter
+            .split_whitespace()
+            .try_fold(0, |total, term| {
+                let term_score = match term.strip_prefix('@') {
+                    Some(label) => request
+                        .tags
+                        .get(&candidate.name)?
+                        .iter()
+                        .filter_map(|tag| score(tag, label, request.options))
+                        .max(),
+                    None if term == ":stale" || term.starts_with("cmd:") => None,
+                    None if term.len() > 1 && term.starts_with('%') => request
+                        .labels
+                        .get(&candidate.name)
+                        .filter(|label| label.is_named(&term[1..]))
+                        .map(|_| 0),
+                    None => score(&candidate.name, term, request.options),
+                }?;
+
+                Some(total + term_score)
+            })
+    }
+}
+
+impl<'de> ZellijWorker<'de> for MatchWorker {
+    fn on_message(&mut self, message: String, payload: String) {
+        if message != REQUEST {
+            return;
+        }
+
+        let Ok(request) = serde_json::from_str::<MatchRequest>(&payload) else {
+            return;
+        };
+
+        let mut scored: Vec<(i64, &Candidate)> = request
+            .candidates
+            .iter()
+            .filter_map(|candidate| Some((MatchWorker::score(&request, candidate)?, candidate)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let response = MatchResponse {
+            generation: request.generation,
+            matches: scored
+                .into_iter()
+                .map(|(_, candidate)| (candidate.session_name.clone(), candidate.position))
+                .collect(),
+        };
+
+        if let Ok(payload) = serde_json::to_string(&response) {
+            post_message_to_plugin(PluginMessage::new_to_plugin(RESPONSE, &payload));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{score, Options};
+
+    fn options(fuzzy: bool) -> Options {
+        Options {
+            ignore_case: true,
+            fuzzy,
+            ignore_accents: false,
+        }
+    }
+
+    #[test]
+    fn lone_anchors_match_everything() {
+        for fuzzy in [false, true] {
+            for needle in ["^", "$", "^$"] {
+                assert_eq!(score("logs", needle, options(fuzzy)), Some(0), "{needle}");
+            }
+        }
+    }
+
+    #[test]
+    fn anchors_match_at_the_ends() {
+        for fuzzy in [false, true] {
+            assert!(score("logs", "^lo", options(fuzzy)).is_some());
+            assert!(score("logs", "^gs", options(fuzzy)).is_none());
+            assert!(score("logs", "gs$", options(fuzzy)).is_some());
+            assert!(score("logs", "lo$", options(fuzzy)).is_none());
+        }
+    }
+}
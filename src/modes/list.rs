@@ -0,0 +1,224 @@
+use super::{Mode, ModeHandler, SearchName};
+use crate::{action::Action, input::TextInput, plural, undo::Change, width, Confirmation, State};
+use owo_colors::OwoColorize;
+use std::fmt::{self, Write};
+use zellij_tile::prelude::*;
+
+/// Browsing the tab list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normal;
+
+/// Typing the filter. `state.filter` follows the input as it's edited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Search {
+    pub input: TextInput,
+}
+
+impl Search {
+    pub fn new(filter: &str) -> Search {
+        Search {
+            input: TextInput::new(filter),
+        }
+    }
+}
+
+/// Selecting a range of tabs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Visual {
+    /// Where the range started; the selection is its other end.
+    pub anchor: crate::Row,
+}
+
+impl ModeHandler for Normal {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match Action::for_key(state, &key) {
+            Some(action) => state.dispatch(action),
+            None => false,
+        }
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        if state.shows_mode_line() {
+            render_filter(state, frame, None)?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Search {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::PageDown | BareKey::PageUp | BareKey::Home | BareKey::End => {
+                state.page(key.bare_key);
+            }
+            BareKey::F(number) => {
+                if !state.apply_preset(number) {
+                    return false;
+                }
+
+                self.input.set(&state.filter);
+            }
+            BareKey::Esc => {
+                state.filter.clear();
+                state.reset_selection();
+
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter if state.search_enter_focuses && state.highlighted.is_some() => {
+                state.activate_highlighted();
+            }
+            BareKey::Enter if state.search_enter_focuses => {
+                state.focus_selected_tab();
+            }
+            BareKey::Enter => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Down | BareKey::Tab => {
+                state.select_down();
+            }
+            BareKey::Char('j') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                state.select_down();
+            }
+            BareKey::Up => {
+                state.select_up();
+            }
+            BareKey::Char('k') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                state.select_up();
+            }
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Alt]) => {
+                state.ignore_case = !state.ignore_case;
+
+                state.reset_selection();
+            }
+            BareKey::Char('f') if key.has_modifiers(&[KeyModifier::Alt]) => {
+                state.fuzzy = !state.fuzzy;
+
+                state.reset_selection();
+            }
+            BareKey::Char('s') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                if state.filter.is_empty() {
+                    state.status = Some("type a filter to save first".to_string());
+                } else {
+                    state.mode = Mode::SearchName(SearchName::default());
+                }
+            }
+            BareKey::Char('x') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                let tab_ids: Vec<usize> =
+                    state.viewable_tabs_iter().map(|tab| tab.tab_id).collect();
+
+                if state.filter.is_empty() || tab_ids.is_empty() {
+                    state.status = Some("no tabs match the filter".to_string());
+                } else {
+                    state.confirm(Confirmation::CloseTabs(tab_ids));
+                }
+            }
+            _ if self.input.handle_key(&key) => {
+                if state.filter != self.input.text() {
+                    state.filter = self.input.text().to_string();
+                    state.reset_selection();
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        render_filter(state, frame, Some(&self.input))?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Visual {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::PageDown | BareKey::PageUp | BareKey::Home | BareKey::End => {
+                state.page(key.bare_key);
+            }
+            BareKey::Esc | BareKey::Char('V') => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                state.select_down();
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                state.select_up();
+            }
+            BareKey::Char(' ') => {
+                let tab_ids: Vec<usize> =
+                    state.visual_range().iter().map(|tab| tab.tab_id).collect();
+                state.history.record(Change::Marks(state.marked.clone()));
+                state.marked.extend(tab_ids);
+
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Char('x') => {
+                let tab_ids: Vec<usize> =
+                    state.visual_range().iter().map(|tab| tab.tab_id).collect();
+                state.mode = Mode::Normal(Normal);
+
+                if !tab_ids.is_empty() {
+                    state.confirm(Confirmation::CloseTabs(tab_ids));
+                }
+            }
+            BareKey::Char('@') => {
+                if !state.start_tagging() {
+                    state.mode = Mode::Normal(Normal);
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "V".cyan().bold(),
+            format!(
+                "-- VISUAL -- {} (x close, @ tag, Space mark)",
+                plural(state.visual_range().len(), "tab")
+            )
+            .dimmed()
+        )?;
+
+        Ok(true)
+    }
+}
+
+/// The filter line shared by `Normal` and `Search` (which passes the input being typed), with the
+/// sort order and matching options.
+fn render_filter(state: &State, frame: &mut String, input: Option<&TextInput>) -> fmt::Result {
+    let line = format!(
+        "{} {} {}",
+        if input.is_some() { "/" } else { ">" }.cyan().bold(),
+        match input {
+            Some(input) => input.render("(filter)"),
+            None if state.filter.is_empty() => "(filter)".dimmed().italic().to_string(),
+            None => format!(
+                "{} {}",
+                state.filter.italic(),
+                " filtered ".black().on_yellow()
+            ),
+        },
+        format!(
+            "[sort: {}{}] [{}, {}]",
+            state.sort_order,
+            if state.sort_reverse { ", reversed" } else { "" },
+            if state.ignore_case {
+                "ignore case"
+            } else {
+                "match case"
+            },
+            if state.fuzzy { "fuzzy" } else { "exact" }
+        )
+        .dimmed()
+    );
+
+    writeln!(frame, "{}", width::truncate(&line, state.cols))
+}
@@ -0,0 +1,101 @@
+mod list;
+mod pickers;
+mod prompts;
+
+pub use list::{Normal, Search, Visual};
+pub use pickers::{session_names, Actions, Layouts, Searches, Sessions, Templates, Workspaces};
+pub use prompts::{Confirm, Launcher, Note, Rename, SearchName, Substitute, Tag, WorkspaceName};
+
+use crate::State;
+use std::fmt;
+use zellij_tile::prelude::*;
+
+/// What keys do while a mode is active and what it draws under the header. Each mode owns the
+/// buffers it edits, so adding one only takes a struct, a handler and a `Mode` variant.
+pub trait ModeHandler {
+    /// Handles a key press, returning whether room needs to render again. Handlers work on a copy
+    /// of the mode and switch modes by replacing `state.mode`.
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool;
+
+    /// Draws the mode's line under the header, returning whether the tab list goes below it.
+    /// Pickers draw their own choices instead.
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mode {
+    Normal(Normal),
+    Search(Search),
+    Actions(Actions),
+    SearchName(SearchName),
+    Searches(Searches),
+    Visual(Visual),
+    Substitute(Substitute),
+    Launcher(Launcher),
+    Layouts(Layouts),
+    Templates(Templates),
+    Tag(Tag),
+    Note(Note),
+    Rename(Rename),
+    Workspaces(Workspaces),
+    WorkspaceName(WorkspaceName),
+    Sessions(Sessions),
+    Confirm(Confirm),
+}
+
+impl Default for Mode {
+    fn default() -> Mode {
+        Mode::Normal(Normal)
+    }
+}
+
+impl Mode {
+    pub fn handler(&self) -> &dyn ModeHandler {
+        match self {
+            Mode::Normal(mode) => mode,
+            Mode::Search(mode) => mode,
+            Mode::Actions(mode) => mode,
+            Mode::SearchName(mode) => mode,
+            Mode::Searches(mode) => mode,
+            Mode::Visual(mode) => mode,
+            Mode::Substitute(mode) => mode,
+            Mode::Launcher(mode) => mode,
+            Mode::Layouts(mode) => mode,
+            Mode::Templates(mode) => mode,
+            Mode::Tag(mode) => mode,
+            Mode::Note(mode) => mode,
+            Mode::Rename(mode) => mode,
+            Mode::Workspaces(mode) => mode,
+            Mode::WorkspaceName(mode) => mode,
+            Mode::Sessions(mode) => mode,
+            Mode::Confirm(mode) => mode,
+        }
+    }
+
+    pub fn handler_mut(&mut self) -> &mut dyn ModeHandler {
+        match self {
+            Mode::Normal(mode) => mode,
+            Mode::Search(mode) => mode,
+            Mode::Actions(mode) => mode,
+            Mode::SearchName(mode) => mode,
+            Mode::Searches(mode) => mode,
+            Mode::Visual(mode) => mode,
+            Mode::Substitute(mode) => mode,
+            Mode::Launcher(mode) => mode,
+            Mode::Layouts(mode) => mode,
+            Mode::Templates(mode) => mode,
+            Mode::Tag(mode) => mode,
+            Mode::Note(mode) => mode,
+            Mode::Rename(mode) => mode,
+            Mode::Workspaces(mode) => mode,
+            Mode::WorkspaceName(mode) => mode,
+            Mode::Sessions(mode) => mode,
+            Mode::Confirm(mode) => mode,
+        }
+    }
+
+    /// Whether the tab list is on screen, so the mouse can pick its rows.
+    pub fn shows_tabs(&self) -> bool {
+        matches!(self, Mode::Normal(_) | Mode::Search(_) | Mode::Visual(_))
+    }
+}
@@ -0,0 +1,532 @@
+use super::{Mode, ModeHandler, Normal};
+use crate::{
+    plural, select::SelectList, storage, width, workspaces, Confirmation, State, SEARCHES_FILE,
+};
+use owo_colors::OwoColorize;
+use std::fmt::{self, Write};
+use zellij_tile::prelude::*;
+
+/// Picking a command from the `action_<key>` configuration keys to run in the selected tab.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Actions {
+    pub list: SelectList<char>,
+}
+
+/// Picking a saved search.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Searches {
+    pub list: SelectList<String>,
+}
+
+/// Picking a layout to open a new tab with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layouts {
+    pub list: SelectList<LayoutInfo>,
+}
+
+/// Picking a tab template.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Templates {
+    /// Picked by name.
+    pub list: SelectList<String>,
+}
+
+/// Picking the active workspace.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Workspaces {
+    pub list: SelectList<Option<String>>,
+}
+
+/// Picking a session to switch to or kill.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sessions {
+    /// Picked by session name.
+    pub list: SelectList<String>,
+}
+
+impl ModeHandler for Actions {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let keys: Vec<char> = state.actions.keys().copied().collect();
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(command) = self
+                    .list
+                    .current(&keys)
+                    .and_then(|c| state.actions.get(c))
+                    .cloned()
+                {
+                    state.run_in_selected_tab(&command);
+                }
+            }
+            BareKey::Down | BareKey::Tab => {
+                self.list.next(&keys);
+            }
+            BareKey::Up => {
+                self.list.previous(&keys);
+            }
+            BareKey::Char(c) if key.has_no_modifiers() => {
+                let Some(command) = state.actions.get(&c).cloned() else {
+                    return false;
+                };
+
+                state.run_in_selected_tab(&command);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {} {}",
+            "!".cyan().bold(),
+            "(action)".dimmed().italic(),
+            match state.selected_tab() {
+                Some(tab) => format!("[in {}]", tab.name),
+                None => String::new(),
+            }
+            .dimmed()
+        )?;
+
+        state.render_choices(
+            frame,
+            state
+                .actions
+                .iter()
+                .map(|(c, command)| format!("{} {command}", c.bold())),
+            self.list
+                .index(&state.actions.keys().copied().collect::<Vec<_>>()),
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Searches {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let names: Vec<String> = state.searches.keys().cloned().collect();
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(filter) = self
+                    .list
+                    .current(&names)
+                    .and_then(|name| state.searches.get(name))
+                {
+                    state.filter = filter.clone();
+                    state.reset_selection();
+                }
+
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Char('d') => {
+                if let Some(index) = self.list.index(&names) {
+                    state.searches.remove(&names[index]);
+                    storage::save(SEARCHES_FILE, &state.searches);
+
+                    let names: Vec<String> = state.searches.keys().cloned().collect();
+                    self.list.select_index(&names, index);
+                }
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                self.list.next(&names);
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                self.list.previous(&names);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "/".cyan().bold(),
+            "(saved search)".dimmed().italic()
+        )?;
+
+        state.render_choices(
+            frame,
+            state
+                .searches
+                .iter()
+                .map(|(name, filter)| format!("{name} {}", filter.dimmed())),
+            self.list
+                .index(&state.searches.keys().cloned().collect::<Vec<_>>()),
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Layouts {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let layouts = state.layout_choices();
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(layout) = self.list.current(&layouts) {
+                    new_tabs_with_layout_info(layout);
+                    state.dismiss();
+                }
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                self.list.next(&layouts);
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                self.list.previous(&layouts);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "+".cyan().bold(),
+            "(layout)".dimmed().italic()
+        )?;
+
+        let layouts = state.layout_choices();
+
+        state.render_choices(
+            frame,
+            layouts.iter().map(|layout| layout.name().to_string()),
+            self.list.index(&layouts),
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Templates {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let names = template_names(state);
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(template) = self
+                    .list
+                    .index(&names)
+                    .and_then(|index| state.templates.get(index))
+                {
+                    template.create(&state.layouts);
+                    state.dismiss();
+                }
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                self.list.next(&names);
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                self.list.previous(&names);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "+".cyan().bold(),
+            "(template)".dimmed().italic()
+        )?;
+
+        state.render_choices(
+            frame,
+            state
+                .templates
+                .iter()
+                .map(|template| format!("{} {}", template.name, template.summary().dimmed())),
+            self.list.index(&template_names(state)),
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Workspaces {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let choices = state.workspace_choices();
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(workspace) = self.list.current(&choices) {
+                    state.activate_workspace(workspace.clone());
+                }
+            }
+            BareKey::Char('d') => {
+                if let Some(Some(workspace)) = self.list.current(&choices) {
+                    if let Some(workspaces) = state.session_workspaces_mut() {
+                        workspaces.remove(workspace);
+                    }
+
+                    storage::save(workspaces::FILE, &state.workspaces);
+                    self.list = SelectList::default();
+                }
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                self.list.next(&choices);
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                self.list.previous(&choices);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        let active = state
+            .session_workspaces()
+            .and_then(|workspaces| workspaces.active.clone());
+
+        writeln!(
+            frame,
+            "{} {}",
+            "~".cyan().bold(),
+            "(workspace)".dimmed().italic()
+        )?;
+
+        let choices = state.workspace_choices();
+
+        state.render_choices(
+            frame,
+            choices.iter().map(|workspace| {
+                let marker = if *workspace == active { "*" } else { " " };
+                match workspace {
+                    Some(name) => {
+                        let members = state
+                            .session_workspaces()
+                            .and_then(|workspaces| workspaces.members.get(name))
+                            .map(|members| members.join(", "))
+                            .unwrap_or_default();
+
+                        format!("{marker} {name} {}", members.dimmed())
+                    }
+                    None => format!("{marker} (all tabs)"),
+                }
+            }),
+            self.list.index(&choices),
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Sessions {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        let names = session_names(state);
+
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                if let Some(session) = self
+                    .list
+                    .index(&names)
+                    .and_then(|index| state.sessions.get(index))
+                {
+                    state.log(format_args!("action switch to session {}", session.name));
+                    state.dismiss();
+
+                    if !session.is_current_session {
+                        switch_session(Some(&session.name));
+                    }
+                }
+            }
+            BareKey::Char('x') => {
+                if let Some(index) = self.list.index(&names) {
+                    state.kill_session(index);
+                }
+            }
+            BareKey::Char('X') => {
+                let names: Vec<String> = state
+                    .other_sessions()
+                    .map(|session| session.name.clone())
+                    .collect();
+
+                if names.is_empty() {
+                    state.status = Some("no other sessions to kill".to_string());
+                } else {
+                    state.confirm(Confirmation::KillSessions(names));
+                }
+            }
+            BareKey::Down | BareKey::Tab | BareKey::Char('j') => {
+                self.list.next(&names);
+            }
+            BareKey::Up | BareKey::Char('k') => {
+                self.list.previous(&names);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "~".cyan().bold(),
+            "(session)".dimmed().italic()
+        )?;
+
+        let selected = self.list.index(&session_names(state));
+
+        state.render_choices(
+            frame,
+            state.sessions.iter().map(|session| {
+                let clients = plural(session.connected_clients, "client");
+
+                if session.is_current_session {
+                    format!(
+                        "● {} {}",
+                        session.name,
+                        format!("(current, {clients})").dimmed()
+                    )
+                    .bold()
+                    .to_string()
+                } else if session.connected_clients > 0 {
+                    format!("  {} {}", session.name, format!("({clients})").dimmed())
+                } else {
+                    format!("  {}", session.name)
+                }
+            }),
+            selected,
+        )?;
+
+        if let Some(session) = selected.and_then(|index| state.sessions.get(index)) {
+            render_session_detail(state, frame, session)?;
+        }
+
+        state.render_status(frame)?;
+        Ok(false)
+    }
+}
+
+/// Pane widths from which the session detail goes beside the session list rather than below it.
+const SIDE_PANEL_COLUMNS: usize = 60;
+
+/// What's in the highlighted session: its tabs, panes and clients. Wide panes show it in a panel
+/// on the right half, drawn with absolute cursor moves next to the list.
+fn render_session_detail(state: &State, frame: &mut String, session: &SessionInfo) -> fmt::Result {
+    let panes = session
+        .panes
+        .panes
+        .values()
+        .flatten()
+        .filter(|pane| !pane.is_plugin && !pane.is_suppressed)
+        .count();
+
+    let mut lines = vec![
+        if session.is_current_session {
+            format!("{} {}", session.name.bold(), "(current)".dimmed())
+        } else {
+            session.name.bold().to_string()
+        },
+        plural(session.connected_clients, "connected client"),
+        format!(
+            "{} · {}",
+            plural(session.tabs.len(), "tab"),
+            plural(panes, "pane")
+        ),
+    ];
+    lines.extend(session.tabs.iter().map(|tab| {
+        let marker = if tab.active { "*" } else { " " };
+
+        format!("{marker} {}", tab.name).dimmed().to_string()
+    }));
+
+    if state.cols < SIDE_PANEL_COLUMNS {
+        // below the session list, its separator and the status line
+        let used =
+            state.list_offset() + state.sessions.len() + 1 + usize::from(state.status.is_some());
+        clip(&mut lines, state.rows.saturating_sub(used));
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(frame, "{}", "─".repeat(state.cols).dimmed())?;
+
+        for line in lines {
+            writeln!(frame, "{}", width::truncate(&line, state.cols))?;
+        }
+
+        return Ok(());
+    }
+
+    let column = state.cols / 2;
+    clip(&mut lines, state.rows.saturating_sub(state.list_offset()));
+
+    // save the cursor, so the status goes on under the list afterwards
+    write!(frame, "\u{1b}7")?;
+    for (index, line) in lines.iter().enumerate() {
+        write!(
+            frame,
+            "\u{1b}[{};{column}H{} {}",
+            state.list_offset() + index + 1,
+            "│".dimmed(),
+            width::truncate(line, state.cols - column - 2)
+        )?;
+    }
+    write!(frame, "\u{1b}8")
+}
+
+/// Cuts `lines` down to `height`, saying how many were left out on the last line kept.
+fn clip(lines: &mut Vec<String>, height: usize) {
+    if lines.len() <= height {
+        return;
+    }
+
+    let left_out = lines.len() + 1 - height;
+    lines.truncate(height.saturating_sub(1));
+    if height > 0 {
+        lines.push(format!("… {left_out} more").dimmed().to_string());
+    }
+}
+
+fn template_names(state: &State) -> Vec<String> {
+    state
+        .templates
+        .iter()
+        .map(|template| template.name.clone())
+        .collect()
+}
+
+pub fn session_names(state: &State) -> Vec<String> {
+    state
+        .sessions
+        .iter()
+        .map(|session| session.name.clone())
+        .collect()
+}
@@ -0,0 +1,333 @@
+use super::{Mode, ModeHandler, Normal, Search};
+use crate::{
+    error::RoomError, input::TextInput, storage, workspaces, Confirmation, State, SEARCHES_FILE,
+};
+use owo_colors::OwoColorize;
+use std::fmt::{self, Write};
+use zellij_tile::prelude::*;
+
+/// Naming the filter to save it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchName {
+    pub input: TextInput,
+}
+
+/// Typing a `s/pattern/replacement/` expression to rename tabs with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Substitute {
+    pub expression: TextInput,
+}
+
+/// Typing a command to launch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Launcher {
+    pub command: TextInput,
+}
+
+/// Editing the tags of some tabs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tag {
+    pub input: TextInput,
+    /// Names of the tabs being tagged.
+    pub targets: Vec<String>,
+}
+
+/// Writing the note of a tab.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Note {
+    pub input: TextInput,
+    /// Name of the tab the note is for.
+    pub target: String,
+}
+
+/// Renaming a single tab, from `action=rename_current`. room closes once it's done.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rename {
+    pub input: TextInput,
+    pub tab_id: usize,
+    /// The tab's name before renaming it.
+    pub name: String,
+}
+
+/// Typing the workspace to add the selected tab to (or remove it from).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkspaceName {
+    pub input: TextInput,
+}
+
+/// Waiting for a `y` before running an action, then going back to the mode it came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Confirm {
+    pub confirmation: Confirmation,
+    pub previous: Box<Mode>,
+}
+
+impl ModeHandler for SearchName {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Search(Search::new(&state.filter));
+            }
+            BareKey::Enter => {
+                let name = self.input.text().trim().to_string();
+
+                if !name.is_empty() {
+                    state.searches.insert(name.clone(), state.filter.clone());
+                    storage::save(SEARCHES_FILE, &state.searches);
+
+                    state.status = Some(format!("saved search {name}"));
+                }
+
+                state.mode = Mode::Search(Search::new(&state.filter));
+            }
+            _ => return self.input.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {} {}",
+            "/".cyan().bold(),
+            self.input.render("(name)"),
+            format!("[save {}]", state.filter).dimmed()
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Substitute {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => match state.substitution_renames(self.expression.text()) {
+                Ok(renames) if renames.is_empty() => {
+                    state.status = Some("no tab names match".to_string());
+                }
+                Ok(renames) => {
+                    state.mode = Mode::Normal(Normal);
+
+                    state.confirm(Confirmation::RenameTabs(renames));
+                }
+                Err(error) => {
+                    state.report_error(RoomError::Input(error));
+                }
+            },
+            _ => return self.expression.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "%".cyan().bold(),
+            self.expression.render("")
+        )?;
+
+        match state.substitution_renames(self.expression.text()) {
+            Ok(renames) => {
+                for (_, name, renamed) in renames {
+                    writeln!(frame, "{} {} {}", name.dimmed(), "→".dimmed(), renamed)?;
+                }
+            }
+            Err(error) => writeln!(frame, "{}", error.dimmed())?,
+        }
+
+        state.render_status(frame)?;
+        Ok(false)
+    }
+}
+
+impl ModeHandler for Launcher {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                state.launch_command(self.command.text());
+            }
+            BareKey::Tab => {
+                state.launch_in_pane = !state.launch_in_pane;
+            }
+            _ => return self.command.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {} {}",
+            ":".cyan().bold(),
+            self.command.render("(command)"),
+            match state.selected_tab() {
+                Some(tab) if state.launch_in_pane => format!("[pane in {}]", tab.name),
+                _ => "[new tab]".to_string(),
+            }
+            .dimmed()
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Tag {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                state.save_tags(self.input.text(), std::mem::take(&mut self.targets));
+
+                state.mode = Mode::Normal(Normal);
+            }
+            _ => return self.input.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, _state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "@".cyan().bold(),
+            self.input.render("(tags)")
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Note {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                state.save_note(&self.target, self.input.text());
+
+                state.mode = Mode::Normal(Normal);
+            }
+            _ => return self.input.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, _state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {} {}",
+            "✎".cyan().bold(),
+            self.input.render("(note)"),
+            format!("[for {}]", self.target).dimmed()
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Rename {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+                state.dismiss();
+            }
+            BareKey::Enter => {
+                let renamed = self.input.text().trim();
+
+                if !renamed.is_empty() && renamed != self.name {
+                    state.rename_tabs(vec![(self.tab_id, self.name.clone(), renamed.to_string())]);
+                }
+
+                state.mode = Mode::Normal(Normal);
+                state.dismiss();
+            }
+            _ => return self.input.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, _state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {} {}",
+            "✎".cyan().bold(),
+            self.input.render("(name)"),
+            format!("[renaming {}]", self.name).dimmed()
+        )?;
+
+        Ok(false)
+    }
+}
+
+impl ModeHandler for WorkspaceName {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Esc => {
+                state.mode = Mode::Normal(Normal);
+            }
+            BareKey::Enter => {
+                let workspace = self.input.text().trim().to_string();
+                let tab_name = state.selected_tab().map(|tab| tab.name.clone());
+
+                if let Some(tab_name) = tab_name.filter(|_| !workspace.is_empty()) {
+                    if let Some(workspaces) = state.session_workspaces_mut() {
+                        workspaces.toggle(&workspace, &tab_name);
+                    }
+
+                    storage::save(workspaces::FILE, &state.workspaces);
+                }
+
+                state.mode = Mode::Normal(Normal);
+            }
+            _ => return self.input.handle_key(&key),
+        }
+
+        true
+    }
+
+    fn render(&self, _state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(
+            frame,
+            "{} {}",
+            "~".cyan().bold(),
+            self.input.render("(add to or remove from workspace)")
+        )?;
+
+        Ok(true)
+    }
+}
+
+impl ModeHandler for Confirm {
+    fn handle_key(&mut self, state: &mut State, key: KeyWithModifier) -> bool {
+        state.mode = std::mem::take(&mut *self.previous);
+
+        if key.is_key_without_modifier(BareKey::Char('y')) {
+            state.run_confirmation(self.confirmation.clone());
+        }
+
+        true
+    }
+
+    fn render(&self, _state: &State, frame: &mut String) -> Result<bool, fmt::Error> {
+        writeln!(frame, "{} {}", "!".red().bold(), self.confirmation.prompt())?;
+
+        Ok(false)
+    }
+}
@@ -0,0 +1,70 @@
+use zellij_tile::prelude::*;
+
+/// A change room asked Zellij for that hasn't shown up in a `TabUpdate` yet. Until every pending
+/// action has settled, updates keep the selection where room put it instead of jumping back to
+/// the active tab.
+#[derive(Debug)]
+pub enum Pending {
+    /// Ids of the tabs being closed.
+    Close(Vec<usize>),
+    /// Ids of the tabs being renamed, with their new names.
+    Rename(Vec<(usize, String)>),
+}
+
+impl Pending {
+    pub fn is_settled(&self, tabs: &[TabInfo]) -> bool {
+        match self {
+            Pending::Close(tab_ids) => !tabs.iter().any(|tab| tab_ids.contains(&tab.tab_id)),
+            Pending::Rename(renames) => renames.iter().all(|(tab_id, name)| {
+                tabs.iter()
+                    .find(|tab| tab.tab_id == *tab_id)
+                    .is_none_or(|tab| &tab.name == name)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pending;
+    use zellij_tile::prelude::*;
+
+    fn tabs(tabs: &[(usize, &str)]) -> Vec<TabInfo> {
+        tabs.iter()
+            .enumerate()
+            .map(|(position, (tab_id, name))| TabInfo {
+                position,
+                tab_id: *tab_id,
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn close_settles_once_every_tab_is_gone() {
+        let pending = Pending::Close(vec![1, 2]);
+
+        assert!(!pending.is_settled(&tabs(&[(1, "a"), (2, "b"), (3, "c")])));
+        assert!(!pending.is_settled(&tabs(&[(2, "b"), (3, "c")])));
+        assert!(pending.is_settled(&tabs(&[(3, "c")])));
+    }
+
+    #[test]
+    fn rename_settles_once_every_name_shows() {
+        let pending = Pending::Rename(vec![(1, "x".to_string()), (2, "y".to_string())]);
+
+        assert!(!pending.is_settled(&tabs(&[(1, "a"), (2, "b")])));
+        assert!(!pending.is_settled(&tabs(&[(1, "x"), (2, "b")])));
+        assert!(pending.is_settled(&tabs(&[(1, "x"), (2, "y")])));
+    }
+
+    #[test]
+    fn renamed_tab_closing_counts_as_settled() {
+        let pending = Pending::Rename(vec![(1, "x".to_string()), (2, "y".to_string())]);
+
+        // tab 1 closed by someone else while tab 2 is still being renamed
+        assert!(!pending.is_settled(&tabs(&[(2, "b")])));
+        assert!(pending.is_settled(&tabs(&[(2, "y")])));
+    }
+}
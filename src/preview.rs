@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use zellij_tile::prelude::*;
+
+pub const WORKER_NAME: &str = "preview";
+pub const REQUEST: &str = "dump_pane";
+pub const RESPONSE: &str = "pane_dump";
+/// How often an open preview is dumped again, to follow commands still printing.
+pub const REFRESH_SECONDS: f64 = 1.0;
+
+#[derive(Serialize, Deserialize)]
+pub struct PreviewRequest {
+    pub tab_position: usize,
+    pub pane_id: u32,
+    pub lines: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PreviewResponse {
+    pub tab_position: usize,
+    pub lines: Vec<String>,
+}
+
+/// Dumps the viewport of a terminal pane off the main thread, so scrolling through
+/// the tab list never waits on the host.
+#[derive(Default, Serialize, Deserialize)]
+pub struct PreviewWorker {}
+
+impl<'de> ZellijWorker<'de> for PreviewWorker {
+    fn on_message(&mut self, message: String, payload: String) {
+        if message != REQUEST {
+            return;
+        }
+
+        let Ok(request) = serde_json::from_str::<PreviewRequest>(&payload) else {
+            return;
+        };
+
+        let mut lines = match get_pane_scrollback(PaneId::Terminal(request.pane_id), false) {
+            Ok(contents) => contents.viewport,
+            Err(_) => Vec::new(),
+        };
+
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+
+        let skip = lines.len().saturating_sub(request.lines);
+        let response = PreviewResponse {
+            tab_position: request.tab_position,
+            lines: lines.split_off(skip),
+        };
+
+        if let Ok(payload) = serde_json::to_string(&response) {
+            post_message_to_plugin(PluginMessage::new_to_plugin(RESPONSE, &payload));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use regex::Regex;
+
+/// The `renumber_format` naming scheme, where `{n}` is the tab's position and `{name}` its name.
+pub struct Numbering {
+    format: String,
+    numbered: Regex,
+}
+
+impl Numbering {
+    /// Fails when `format` has `{name}` more than once, which can't be stripped back off.
+    pub fn new(format: &str) -> Result<Numbering, regex::Error> {
+        let pattern = regex::escape(format)
+            .replace(r"\{n\}", r"\d+")
+            .replace(r"\{name\}", "(?P<name>.*)");
+
+        Ok(Numbering {
+            format: format.to_string(),
+            numbered: Regex::new(&format!("^{pattern}$"))?,
+        })
+    }
+
+    /// The name without a number this scheme added earlier.
+    fn strip<'a>(&self, name: &'a str) -> &'a str {
+        self.numbered
+            .captures(name)
+            .and_then(|captures| captures.name("name"))
+            .map_or(name, |stripped| stripped.as_str())
+    }
+
+    /// The name numbered for `position`, or `None` when it already is.
+    pub fn apply(&self, position: usize, name: &str) -> Option<String> {
+        let renamed = self
+            .format
+            .replace("{n}", &(position + 1).to_string())
+            .replace("{name}", self.strip(name));
+
+        Some(renamed).filter(|renamed| renamed != name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Numbering;
+
+    #[test]
+    fn renumbering_replaces_an_earlier_number() {
+        let numbering = Numbering::new("{n}. {name}").unwrap();
+
+        assert_eq!(numbering.apply(0, "3. logs"), Some("1. logs".to_string()));
+        assert_eq!(numbering.apply(0, "1. logs"), None);
+    }
+
+    #[test]
+    fn repeated_name_is_an_error() {
+        assert!(Numbering::new("{name} ({name})").is_err());
+    }
+}
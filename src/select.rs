@@ -0,0 +1,180 @@
+/// The selection in a list of choices. It's kept by value rather than by index, so it stays on the
+/// same choice when the list is re-sorted, filtered or updated under it. Nothing selected stands
+/// for the first choice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectList<T> {
+    selected: Option<T>,
+}
+
+impl<T> Default for SelectList<T> {
+    fn default() -> SelectList<T> {
+        SelectList { selected: None }
+    }
+}
+
+impl<T: Clone + PartialEq> SelectList<T> {
+    pub fn new(selected: Option<T>) -> SelectList<T> {
+        SelectList { selected }
+    }
+
+    pub fn into_selected(self) -> Option<T> {
+        self.selected
+    }
+
+    /// Where the selection is in `items`, or `None` when it's no longer there.
+    pub fn index(&self, items: &[T]) -> Option<usize> {
+        match &self.selected {
+            Some(selected) => items.iter().position(|item| item == selected),
+            None if items.is_empty() => None,
+            None => Some(0),
+        }
+    }
+
+    pub fn current<'a>(&self, items: &'a [T]) -> Option<&'a T> {
+        self.index(items).and_then(|index| items.get(index))
+    }
+
+    /// Selects the choice at `index`, or the last one when there are fewer.
+    pub fn select_index(&mut self, items: &[T], index: usize) {
+        self.selected = items.get(index.min(items.len().saturating_sub(1))).cloned();
+    }
+
+    /// Selects the next choice, wrapping around to the first one.
+    pub fn next(&mut self, items: &[T]) {
+        if items.is_empty() {
+            return;
+        }
+
+        let next = self
+            .index(items)
+            .map_or(0, |index| (index + 1) % items.len());
+        self.selected = Some(items[next].clone());
+    }
+
+    /// Selects the previous choice, wrapping around to the last one.
+    pub fn previous(&mut self, items: &[T]) {
+        if items.is_empty() {
+            return;
+        }
+
+        let previous = self.index(items).map_or(items.len() - 1, |index| {
+            (index + items.len() - 1) % items.len()
+        });
+        self.selected = Some(items[previous].clone());
+    }
+
+    /// Moves the selection by `offset` choices, stopping at either end instead of wrapping.
+    pub fn move_by(&mut self, items: &[T], offset: isize) {
+        let index = self.index(items).unwrap_or(0).saturating_add_signed(offset);
+
+        self.select_index(items, index);
+    }
+
+    /// Falls back to the first choice when the selected one has gone from `items`.
+    pub fn retain(&mut self, items: &[T]) {
+        if self.index(items).is_none() {
+            self.selected = None;
+        }
+    }
+
+    /// Moves a viewport that starts at `scroll` just enough to keep the selection in it, without
+    /// leaving empty space after the last choice. `fitting(start)` is how many choices from
+    /// `start` fit in the viewport.
+    pub fn scroll(
+        &self,
+        items: &[T],
+        mut scroll: usize,
+        fitting: impl Fn(usize) -> usize,
+    ) -> usize {
+        if let Some(index) = self.index(items) {
+            if index < scroll {
+                scroll = index;
+            }
+
+            while index >= scroll + fitting(scroll) {
+                scroll += 1;
+            }
+        }
+
+        while scroll > 0 && scroll - 1 + fitting(scroll - 1) >= items.len() {
+            scroll -= 1;
+        }
+
+        scroll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SelectList;
+
+    const ITEMS: [char; 4] = ['a', 'b', 'c', 'd'];
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let mut list = SelectList::new(Some('d'));
+
+        list.next(&ITEMS);
+        assert_eq!(list.current(&ITEMS), Some(&'a'));
+
+        list.previous(&ITEMS);
+        assert_eq!(list.current(&ITEMS), Some(&'d'));
+    }
+
+    #[test]
+    fn move_by_stops_at_either_end() {
+        let mut list = SelectList::new(Some('b'));
+
+        list.move_by(&ITEMS, 10);
+        assert_eq!(list.current(&ITEMS), Some(&'d'));
+
+        list.move_by(&ITEMS, -10);
+        assert_eq!(list.current(&ITEMS), Some(&'a'));
+
+        list.move_by(&ITEMS, isize::MAX);
+        assert_eq!(list.current(&ITEMS), Some(&'d'));
+
+        list.move_by(&ITEMS, isize::MIN);
+        assert_eq!(list.current(&ITEMS), Some(&'a'));
+    }
+
+    #[test]
+    fn select_index_past_the_end_selects_the_last() {
+        let mut list = SelectList::default();
+
+        list.select_index(&ITEMS, 9);
+        assert_eq!(list.current(&ITEMS), Some(&'d'));
+
+        list.select_index(&[], 0);
+        assert_eq!(list.current(&ITEMS), Some(&'a'));
+    }
+
+    #[test]
+    fn retain_keeps_the_selection_or_falls_back_to_the_first() {
+        let mut list = SelectList::new(Some('c'));
+
+        list.retain(&['c', 'd']);
+        assert_eq!(list.current(&['c', 'd']), Some(&'c'));
+
+        list.retain(&['a', 'b']);
+        assert_eq!(list.current(&['a', 'b']), Some(&'a'));
+    }
+
+    #[test]
+    fn index_is_none_for_an_empty_list() {
+        assert_eq!(SelectList::<char>::default().index(&[]), None);
+        assert_eq!(SelectList::new(Some('a')).index(&[]), None);
+        assert_eq!(SelectList::<char>::default().current(&[]), None);
+    }
+
+    #[test]
+    fn scroll_keeps_the_selection_in_view() {
+        let fitting = |start: usize| 2.min(ITEMS.len() - start);
+
+        assert_eq!(SelectList::new(Some('d')).scroll(&ITEMS, 0, fitting), 2);
+        assert_eq!(SelectList::new(Some('a')).scroll(&ITEMS, 2, fitting), 0);
+        assert_eq!(SelectList::new(Some('b')).scroll(&ITEMS, 1, fitting), 1);
+        // nothing is left below the last choice
+        assert_eq!(SelectList::new(Some('d')).scroll(&ITEMS, 3, fitting), 2);
+    }
+}
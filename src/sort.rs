@@ -0,0 +1,68 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum SortOrder {
+    #[default]
+    Position,
+    Alphabetical,
+    Recent,
+    Panes,
+}
+
+impl SortOrder {
+    pub fn next(self) -> SortOrder {
+        match self {
+            SortOrder::Position => SortOrder::Alphabetical,
+            SortOrder::Alphabetical => SortOrder::Recent,
+            SortOrder::Recent => SortOrder::Panes,
+            SortOrder::Panes => SortOrder::Position,
+        }
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<SortOrder, ()> {
+        match value {
+            "position" => Ok(SortOrder::Position),
+            "alphabetical" => Ok(SortOrder::Alphabetical),
+            "recent" => Ok(SortOrder::Recent),
+            "panes" => Ok(SortOrder::Panes),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortOrder::Position => write!(f, "position"),
+            SortOrder::Alphabetical => write!(f, "alphabetical"),
+            SortOrder::Recent => write!(f, "recent"),
+            SortOrder::Panes => write!(f, "panes"),
+        }
+    }
+}
+
+/// Where `pin_active_tab` keeps the active tab, whatever the sort order.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ActivePin {
+    #[default]
+    None,
+    Top,
+    Bottom,
+}
+
+impl std::str::FromStr for ActivePin {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<ActivePin, ()> {
+        match value {
+            "none" => Ok(ActivePin::None),
+            "top" => Ok(ActivePin::Top),
+            "bottom" => Ok(ActivePin::Bottom),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, io::Write, path::PathBuf};
+
+/// Zellij maps this folder to a directory shared by every instance of the plugin.
+const DATA_DIR: &str = "/data";
+
+fn path(file: &str) -> PathBuf {
+    PathBuf::from(DATA_DIR).join(file)
+}
+
+/// Reads a JSON file from the plugin data dir, falling back to the default when it's missing or
+/// unreadable so a corrupt file never keeps room from opening.
+pub fn load<T: DeserializeOwned + Default>(file: &str) -> T {
+    fs::read_to_string(path(file))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(file: &str, value: &T) {
+    if let Ok(contents) = serde_json::to_string_pretty(value) {
+        let _ = fs::write(path(file), contents);
+    }
+}
+
+/// Appends a line to a plain text file in the plugin data dir.
+pub fn append(file: &str, line: &str) {
+    if let Ok(mut file) = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path(file))
+    {
+        let _ = writeln!(file, "{line}");
+    }
+}
@@ -0,0 +1,152 @@
+use regex::Regex;
+
+/// A sed style `s/pattern/replacement/flags` expression applied to tab names. Any character can
+/// stand in for `/`, `g` replaces every match instead of the first one, and in the replacement
+/// `\1` to `\9` are the pattern's groups and `&` the whole match, as in sed.
+pub struct Substitution {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    pub fn parse(expression: &str) -> Result<Substitution, String> {
+        let mut chars = expression.chars();
+        if chars.next() != Some('s') {
+            return Err("expected s/pattern/replacement/".to_string());
+        }
+
+        let Some(delimiter) = chars.next() else {
+            return Err("expected s/pattern/replacement/".to_string());
+        };
+
+        let parts = split(chars.as_str(), delimiter);
+        let [pattern, replacement, flags @ ..] = parts.as_slice() else {
+            return Err("expected s/pattern/replacement/".to_string());
+        };
+
+        let flags = flags.concat();
+        if let Some(flag) = flags.chars().find(|flag| !"gi".contains(*flag)) {
+            return Err(format!("unknown flag {flag}"));
+        }
+
+        let pattern = if flags.contains('i') {
+            format!("(?i){pattern}")
+        } else {
+            pattern.clone()
+        };
+
+        Ok(Substitution {
+            regex: Regex::new(&pattern).map_err(|error| error.to_string())?,
+            replacement: replacement_template(replacement),
+            global: flags.contains('g'),
+        })
+    }
+
+    /// The new name, or `None` when the pattern doesn't change it.
+    pub fn apply(&self, name: &str) -> Option<String> {
+        let renamed = if self.global {
+            self.regex.replace_all(name, self.replacement.as_str())
+        } else {
+            self.regex.replace(name, self.replacement.as_str())
+        };
+
+        Some(renamed.into_owned()).filter(|renamed| renamed != name)
+    }
+}
+
+/// Turns a sed replacement into the regex crate's syntax: `\N` becomes `${N}`, `&` becomes `${0}`,
+/// and anything else, `$` included, is kept as written. A `\` before any other character (such as
+/// `\&`) keeps that character as is.
+fn replacement_template(replacement: &str) -> String {
+    let mut template = String::new();
+    let mut chars = replacement.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(group @ '0'..='9') => template.push_str(&format!("${{{group}}}")),
+                Some('$') => template.push_str("$$"),
+                Some(c) => template.push(c),
+                None => template.push('\\'),
+            },
+            '&' => template.push_str("${0}"),
+            '$' => template.push_str("$$"),
+            c => template.push(c),
+        }
+    }
+
+    template
+}
+
+/// Splits on `delimiter`, keeping `\` escaped delimiters (unescaped) inside the parts.
+fn split(expression: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = expression.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&delimiter) {
+            parts.last_mut().unwrap().push(delimiter);
+            chars.next();
+        } else if c == delimiter {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Substitution;
+
+    fn rename(expression: &str, name: &str) -> Option<String> {
+        Substitution::parse(expression).unwrap().apply(name)
+    }
+
+    #[test]
+    fn any_character_can_delimit() {
+        assert_eq!(rename("s|/|-|", "src/api"), Some("src-api".to_string()));
+    }
+
+    #[test]
+    fn escaped_delimiters_are_part_of_the_pattern() {
+        assert_eq!(rename(r"s/\//-/", "src/api"), Some("src-api".to_string()));
+    }
+
+    #[test]
+    fn g_replaces_every_match() {
+        assert_eq!(rename("s/a/o/", "banana"), Some("bonana".to_string()));
+        assert_eq!(rename("s/a/o/g", "banana"), Some("bonono".to_string()));
+        assert_eq!(rename("s/A/o/gi", "banana"), Some("bonono".to_string()));
+    }
+
+    #[test]
+    fn unknown_flags_are_errors() {
+        assert_eq!(
+            Substitution::parse("s/a/o/x").err(),
+            Some("unknown flag x".to_string())
+        );
+    }
+
+    #[test]
+    fn trailing_delimiter_is_optional() {
+        assert_eq!(rename("s/-old$/", "api-old"), Some("api".to_string()));
+        assert_eq!(rename("s/a/o", "api"), Some("opi".to_string()));
+        assert!(Substitution::parse("s/a").is_err());
+        assert!(Substitution::parse("x/a/o/").is_err());
+    }
+
+    #[test]
+    fn groups_and_the_whole_match_are_referenced_sed_style() {
+        assert_eq!(
+            rename(r"s/(\w+)-(\w+)/\2-\1/", "api-old"),
+            Some("old-api".to_string())
+        );
+        assert_eq!(rename("s/api/[&]/", "api"), Some("[api]".to_string()));
+        assert_eq!(rename(r"s/api/\&/", "api"), Some("&".to_string()));
+        assert_eq!(rename(r"s/(api)/$1x/", "api"), Some("$1x".to_string()));
+    }
+}
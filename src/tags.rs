@@ -0,0 +1,24 @@
+use owo_colors::{AnsiColors, OwoColorize};
+
+pub const FILE: &str = "tags.json";
+
+const PALETTE: [AnsiColors; 6] = [
+    AnsiColors::Blue,
+    AnsiColors::Magenta,
+    AnsiColors::Green,
+    AnsiColors::Yellow,
+    AnsiColors::Cyan,
+    AnsiColors::Red,
+];
+
+/// Renders a tag as a colored chip, always picking the same color for the same label.
+pub fn chip(tag: &str) -> String {
+    let hash = tag.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+
+    format!(" {tag} ")
+        .black()
+        .on_color(PALETTE[hash % PALETTE.len()])
+        .to_string()
+}
@@ -0,0 +1,229 @@
+use crate::error::RoomError;
+use std::collections::BTreeMap;
+use zellij_tile::prelude::*;
+
+const PREFIX: &str = "template_";
+
+/// A tab recipe from the `template_<name>` configuration keys, written as
+/// `"layout=ide, cwd=~/work/api, command=cargo run"`. Every field is optional.
+pub struct Template {
+    pub name: String,
+    pub layout: Option<String>,
+    pub cwd: Option<String>,
+    pub command: Option<String>,
+}
+
+impl Template {
+    /// Templates from every `template_<name>` key. Fields that aren't `layout`, `cwd` or `command`
+    /// are left out and passed to `report`.
+    pub fn from_configuration(
+        configuration: &BTreeMap<String, String>,
+        mut report: impl FnMut(RoomError),
+    ) -> Vec<Template> {
+        configuration
+            .iter()
+            .filter_map(|(key, value)| {
+                let name = key.strip_prefix(PREFIX)?;
+
+                let mut template = Template {
+                    name: name.to_string(),
+                    layout: None,
+                    cwd: None,
+                    command: None,
+                };
+
+                for field in value.split(',').filter(|field| !field.trim().is_empty()) {
+                    let (field_key, field_value) = field.split_once('=').unwrap_or((field, ""));
+                    let field_value =
+                        Some(field_value.trim().to_string()).filter(|value| !value.is_empty());
+
+                    match field_key.trim() {
+                        "layout" => template.layout = field_value,
+                        "cwd" => template.cwd = field_value,
+                        "command" => template.command = field_value,
+                        _ => report(RoomError::Config {
+                            key: key.clone(),
+                            value: field.trim().to_string(),
+                        }),
+                    }
+                }
+
+                Some(template)
+            })
+            .collect()
+    }
+
+    pub fn summary(&self) -> String {
+        [
+            self.layout
+                .as_ref()
+                .map(|layout| format!("layout={layout}")),
+            self.cwd.as_ref().map(|cwd| format!("cwd={cwd}")),
+            self.command
+                .as_ref()
+                .map(|command| format!("command={command}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<String>>()
+        .join(", ")
+    }
+
+    fn command_to_run(&self) -> Option<CommandToRun> {
+        let mut words = self.command.as_ref()?.split_whitespace();
+
+        Some(CommandToRun {
+            path: words.next()?.into(),
+            args: words.map(String::from).collect(),
+            cwd: self.cwd.as_ref().map(Into::into),
+        })
+    }
+
+    /// Opens the tab this template describes.
+    ///
+    /// Without a layout the whole tab is described as a stringified layout, so the command (if any)
+    /// becomes its only pane. Named layouts can't be given a cwd, so only the command pane opened
+    /// on top of them starts there.
+    pub fn create(&self, layouts: &[LayoutInfo]) {
+        let Some(layout) = &self.layout else {
+            new_tabs_with_layout(&self.to_kdl());
+            return;
+        };
+
+        let layout = layouts
+            .iter()
+            .find(|info| info.name() == layout)
+            .cloned()
+            .unwrap_or_else(|| LayoutInfo::File(layout.clone(), LayoutMetadata::default()));
+
+        for tab_id in new_tabs_with_layout_info(layout) {
+            rename_tab_with_id(tab_id as u64, &self.name);
+        }
+
+        if let Some(command_to_run) = self.command_to_run() {
+            open_command_pane(command_to_run, BTreeMap::new());
+        }
+    }
+
+    fn to_kdl(&self) -> String {
+        let mut tab = format!("tab name={}", quote(&self.name));
+
+        if let Some(cwd) = &self.cwd {
+            tab.push_str(&format!(" cwd={}", quote(cwd)));
+        }
+
+        let pane = match self.command_to_run() {
+            Some(CommandToRun { path, args, .. }) if args.is_empty() => {
+                format!("pane command={}", quote(&path.to_string_lossy()))
+            }
+            Some(CommandToRun { path, args, .. }) => format!(
+                "pane command={} {{\nargs {}\n}}",
+                quote(&path.to_string_lossy()),
+                args.iter()
+                    .map(|arg| quote(arg))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            ),
+            None => "pane".to_string(),
+        };
+
+        format!("layout {{\n{tab} {{\n{pane}\n}}\n}}")
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use std::collections::BTreeMap;
+
+    fn templates(entries: &[(&str, &str)]) -> Vec<Template> {
+        let configuration: BTreeMap<String, String> = entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Template::from_configuration(&configuration, |error| panic!("{error}"))
+    }
+
+    #[test]
+    fn fields_are_parsed_from_the_value() {
+        let parsed = templates(&[
+            (
+                "template_api",
+                " layout = ide, cwd=~/work/api,command=cargo run ",
+            ),
+            ("template_bare", ""),
+            ("theme", "nord"),
+        ]);
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].name, "api");
+        assert_eq!(parsed[0].layout.as_deref(), Some("ide"));
+        assert_eq!(parsed[0].cwd.as_deref(), Some("~/work/api"));
+        assert_eq!(parsed[0].command.as_deref(), Some("cargo run"));
+        assert_eq!(parsed[1].name, "bare");
+        assert_eq!(parsed[1].summary(), "");
+    }
+
+    #[test]
+    fn unknown_fields_are_reported() {
+        let configuration = BTreeMap::from([(
+            "template_api".to_string(),
+            "cwd=~/api, shell=fish, nvim".to_string(),
+        )]);
+        let mut errors = Vec::new();
+
+        let parsed = Template::from_configuration(&configuration, |error| {
+            errors.push(error.to_string());
+        });
+
+        assert_eq!(parsed[0].cwd.as_deref(), Some("~/api"));
+        assert_eq!(
+            errors,
+            [
+                "invalid template_api \"shell=fish\", using the default",
+                "invalid template_api \"nvim\", using the default",
+            ]
+        );
+    }
+
+    #[test]
+    fn kdl_quotes_names_and_arguments() {
+        let template = Template {
+            name: r#"say "hi""#.to_string(),
+            layout: None,
+            cwd: Some(r"C:\work".to_string()),
+            command: Some(r#"echo "a\b""#.to_string()),
+        };
+
+        assert_eq!(
+            template.to_kdl(),
+            r#"layout {
+tab name="say \"hi\"" cwd="C:\\work" {
+pane command="echo" {
+args "\"a\\b\""
+}
+}
+}"#
+        );
+    }
+
+    #[test]
+    fn kdl_leaves_out_args_for_a_bare_command() {
+        let template = Template {
+            name: "scratch".to_string(),
+            layout: None,
+            cwd: None,
+            command: Some("htop".to_string()),
+        };
+
+        assert_eq!(
+            template.to_kdl(),
+            "layout {\ntab name=\"scratch\" {\npane command=\"htop\"\n}\n}"
+        );
+    }
+}
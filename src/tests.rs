@@ -0,0 +1,660 @@
+//! Feeds recorded sequences of Zellij events into `State::update` and checks what room asked
+//! Zellij to do, through a `Host` that records the calls instead of making them.
+
+use crate::action::{Action, LaunchAction};
+use crate::custom::Step;
+use crate::host::{Host, HostHandle};
+use crate::modes::Mode;
+use crate::width;
+use crate::{duration, parse_duration, State};
+use std::cell::RefCell;
+use std::rc::Rc;
+use zellij_tile::prelude::*;
+
+#[derive(Debug, PartialEq)]
+enum Call {
+    CloseTab(usize),
+    FocusTab(usize),
+    RenameTab(usize, String),
+    ClosePlugin,
+    HidePlugin,
+    ShowPlugin,
+    PostToWorker(String),
+    SetTimeout,
+    OpenCommandPane(String),
+}
+
+#[derive(Default)]
+struct Recorder {
+    calls: RefCell<Vec<Call>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<Call> {
+        self.calls.take()
+    }
+}
+
+impl Host for Recorder {
+    fn close_tab(&self, tab_id: usize) {
+        self.calls.borrow_mut().push(Call::CloseTab(tab_id));
+    }
+
+    fn focus_tab(&self, position: usize) {
+        self.calls.borrow_mut().push(Call::FocusTab(position));
+    }
+
+    fn rename_tab(&self, tab_id: usize, name: &str) {
+        self.calls
+            .borrow_mut()
+            .push(Call::RenameTab(tab_id, name.to_string()));
+    }
+
+    fn close_plugin(&self) {
+        self.calls.borrow_mut().push(Call::ClosePlugin);
+    }
+
+    fn hide_plugin(&self) {
+        self.calls.borrow_mut().push(Call::HidePlugin);
+    }
+
+    fn show_plugin(&self) {
+        self.calls.borrow_mut().push(Call::ShowPlugin);
+    }
+
+    fn open_command_pane(&self, command_to_run: CommandToRun) {
+        self.calls.borrow_mut().push(Call::OpenCommandPane(
+            command_to_run.path.to_string_lossy().into_owned(),
+        ));
+    }
+
+    fn post_to_worker(&self, _worker_name: &str, message: &str, _payload: &str) {
+        self.calls
+            .borrow_mut()
+            .push(Call::PostToWorker(message.to_string()));
+    }
+
+    fn set_timeout(&self, _seconds: f64) {
+        self.calls.borrow_mut().push(Call::SetTimeout);
+    }
+}
+
+/// Tabs with these names, in order, with ids starting at 10 so they never match positions.
+fn tabs(names: &[&str], active: usize) -> Vec<TabInfo> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(position, name)| TabInfo {
+            position,
+            tab_id: position + 10,
+            name: name.to_string(),
+            active: position == active,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Tabs with these ids and names, for updates after some have closed.
+fn remaining(tabs: &[(usize, &str)], active: usize) -> Vec<TabInfo> {
+    tabs.iter()
+        .enumerate()
+        .map(|(position, (tab_id, name))| TabInfo {
+            position,
+            tab_id: *tab_id,
+            name: name.to_string(),
+            active: position == active,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn start(tab_info: Vec<TabInfo>) -> (State, Rc<Recorder>) {
+    let recorder = Rc::new(Recorder::default());
+    let mut state = State {
+        host: HostHandle(recorder.clone()),
+        // as `load` leaves it
+        visible: true,
+        ..Default::default()
+    };
+
+    state.update(Event::TabUpdate(tab_info));
+    (state, recorder)
+}
+
+fn press(state: &mut State, bare_key: BareKey) {
+    state.update(Event::Key(KeyWithModifier::new(bare_key)));
+}
+
+fn type_text(state: &mut State, text: &str) {
+    for c in text.chars() {
+        press(state, BareKey::Char(c));
+    }
+}
+
+fn selected_name(state: &State) -> Option<String> {
+    state.selected_tab().map(|tab| tab.name.clone())
+}
+
+#[test]
+fn enter_switches_to_the_selected_tab() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c"], 0));
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Enter);
+
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(1)]);
+}
+
+#[test]
+fn closing_waits_for_confirmation() {
+    let (mut state, recorder) = start(tabs(&["a", "b"], 0));
+
+    press(&mut state, BareKey::Char('x'));
+    assert!(matches!(state.mode, Mode::Confirm(_)));
+
+    press(&mut state, BareKey::Char('n'));
+    assert!(matches!(state.mode, Mode::Normal(_)));
+    assert_eq!(recorder.take(), []);
+}
+
+#[test]
+fn delete_then_navigate_before_the_update() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c"], 0));
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('x'));
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::CloseTab(11)]);
+
+    // moving while Zellij hasn't closed the tab yet
+    press(&mut state, BareKey::Char('j'));
+    state.update(Event::TabUpdate(remaining(&[(10, "a"), (12, "c")], 0)));
+
+    assert_eq!(selected_name(&state).as_deref(), Some("c"));
+
+    // the next close targets the tab by id, not its old position
+    press(&mut state, BareKey::Char('x'));
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::CloseTab(12)]);
+}
+
+#[test]
+fn updates_for_other_changes_keep_the_pending_selection() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c"], 0));
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('x'));
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::CloseTab(11)]);
+
+    // an update that arrives before the close still lists the tab
+    state.update(Event::TabUpdate(tabs(&["a", "b", "c"], 0)));
+    assert_eq!(selected_name(&state).as_deref(), Some("b"));
+
+    state.update(Event::TabUpdate(remaining(&[(10, "a"), (12, "c")], 0)));
+    assert_eq!(selected_name(&state).as_deref(), Some("c"));
+}
+
+#[test]
+fn filter_then_delete_closes_only_matches() {
+    let (mut state, recorder) = start(tabs(&["api", "web", "app-db"], 1));
+
+    press(&mut state, BareKey::Char('/'));
+    type_text(&mut state, "ap");
+    press(&mut state, BareKey::Enter);
+
+    assert!(matches!(state.mode, Mode::Normal(_)));
+    assert_eq!(state.filter, "ap");
+    assert_eq!(selected_name(&state).as_deref(), Some("api"));
+
+    press(&mut state, BareKey::Char('x'));
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::CloseTab(10)]);
+
+    state.update(Event::TabUpdate(remaining(
+        &[(11, "web"), (12, "app-db")],
+        0,
+    )));
+    assert_eq!(selected_name(&state).as_deref(), Some("app-db"));
+}
+
+#[test]
+fn undo_renames_back() {
+    let (mut state, recorder) = start(tabs(&["a", "b"], 0));
+
+    press(&mut state, BareKey::Char('%'));
+    type_text(&mut state, "a/z/");
+    press(&mut state, BareKey::Enter);
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::RenameTab(10, "z".to_string())]);
+
+    state.update(Event::TabUpdate(remaining(&[(10, "z"), (11, "b")], 0)));
+    press(&mut state, BareKey::Char('u'));
+    assert_eq!(recorder.take(), [Call::RenameTab(10, "a".to_string())]);
+}
+
+#[test]
+fn ctrl_u_clears_the_prefilled_filter() {
+    let (mut state, _) = start(tabs(&["api", "web"], 0));
+
+    press(&mut state, BareKey::Char('/'));
+    type_text(&mut state, "we");
+    press(&mut state, BareKey::Enter);
+
+    press(&mut state, BareKey::Char('/'));
+    state.update(Event::Key(
+        KeyWithModifier::new(BareKey::Char('u')).with_ctrl_modifier(),
+    ));
+    assert_eq!(state.filter, "");
+
+    type_text(&mut state, "ap");
+    assert_eq!(state.filter, "ap");
+}
+
+#[test]
+fn hidden_mode_line_shows_a_confirmed_filter() {
+    let (mut state, _) = start(tabs(&["api", "web"], 0));
+    state.show_mode_line = false;
+
+    let mut frame = String::new();
+    state.render_frame(&mut frame, 10, 40).unwrap();
+    assert!(!frame.contains("(filter)"));
+    assert_eq!(state.list_offset(), 0);
+
+    press(&mut state, BareKey::Char('/'));
+    type_text(&mut state, "we");
+    press(&mut state, BareKey::Enter);
+    assert!(matches!(state.mode, Mode::Normal(_)));
+
+    frame.clear();
+    state.render_frame(&mut frame, 10, 40).unwrap();
+    assert!(frame.contains(" filtered "));
+    assert_eq!(state.list_offset(), 1);
+}
+
+#[test]
+fn esc_clears_the_filter_before_closing() {
+    let (mut state, recorder) = start(tabs(&["api", "web"], 0));
+
+    press(&mut state, BareKey::Char('/'));
+    type_text(&mut state, "we");
+    press(&mut state, BareKey::Enter);
+
+    press(&mut state, BareKey::Esc);
+    assert_eq!(state.filter, "");
+    assert_eq!(recorder.take(), []);
+
+    press(&mut state, BareKey::Esc);
+    assert_eq!(recorder.take(), [Call::ClosePlugin]);
+}
+
+#[test]
+fn rename_current_renames_the_active_tab_then_closes() {
+    let recorder = Rc::new(Recorder::default());
+    let mut state = State {
+        host: HostHandle(recorder.clone()),
+        visible: true,
+        launch_action: Some(LaunchAction::RenameCurrent),
+        ..Default::default()
+    };
+
+    state.update(Event::TabUpdate(tabs(&["a", "b"], 1)));
+    assert!(matches!(state.mode, Mode::Rename(_)));
+
+    type_text(&mut state, "-2");
+    press(&mut state, BareKey::Enter);
+    assert_eq!(
+        recorder.take(),
+        [Call::RenameTab(11, "b-2".to_string()), Call::ClosePlugin]
+    );
+}
+
+#[test]
+fn dash_switches_back_to_the_previous_tab() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c"], 0));
+    state.session_name = Some("work".to_string());
+
+    state.update(Event::TabUpdate(tabs(&["a", "b", "c"], 0)));
+    state.update(Event::TabUpdate(tabs(&["a", "b", "c"], 2)));
+
+    press(&mut state, BareKey::Char('-'));
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(0)]);
+}
+
+#[test]
+fn delete_current_closes_the_active_tab_without_drawing() {
+    let recorder = Rc::new(Recorder::default());
+    let mut state = State {
+        host: HostHandle(recorder.clone()),
+        visible: true,
+        launch_action: Some(LaunchAction::DeleteCurrent),
+        silent: true,
+        ..Default::default()
+    };
+
+    state.update(Event::TabUpdate(tabs(&["a", "b"], 1)));
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::CloseTab(11)]);
+}
+
+#[test]
+fn launch_action_waits_for_an_active_tab() {
+    let recorder = Rc::new(Recorder::default());
+    let mut state = State {
+        host: HostHandle(recorder.clone()),
+        visible: true,
+        launch_action: Some(LaunchAction::DeleteCurrent),
+        silent: true,
+        ..Default::default()
+    };
+
+    state.update(Event::TabUpdate(tabs(&["a", "b"], usize::MAX)));
+    assert_eq!(recorder.take(), []);
+
+    state.update(Event::TabUpdate(tabs(&["a", "b"], 0)));
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::CloseTab(10)]);
+}
+
+#[test]
+fn brackets_go_back_and_forward_through_switched_tabs() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c"], 0));
+    state.session_name = Some("work".to_string());
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Enter);
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(2)]);
+
+    state.update(Event::TabUpdate(tabs(&["a", "b", "c"], 2)));
+    press(&mut state, BareKey::Char('['));
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(0)]);
+
+    state.update(Event::TabUpdate(tabs(&["a", "b", "c"], 0)));
+    press(&mut state, BareKey::Char(']'));
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(2)]);
+}
+
+#[test]
+fn bindings_match_modifiers() {
+    let (mut state, _) = start(tabs(&["a", "b", "dev"], 0));
+    state.bindings = vec![("Alt d".parse().unwrap(), Action::SelectLast)];
+
+    press(&mut state, BareKey::Char('d'));
+    assert_eq!(selected_name(&state).as_deref(), Some("dev"));
+
+    state.update(Event::Key(
+        KeyWithModifier::new(BareKey::Char('k')).with_ctrl_modifier(),
+    ));
+    assert_eq!(selected_name(&state).as_deref(), Some("dev"));
+
+    press(&mut state, BareKey::Home);
+    state.update(Event::Key(
+        KeyWithModifier::new(BareKey::Char('d')).with_alt_modifier(),
+    ));
+    assert_eq!(selected_name(&state).as_deref(), Some("dev"));
+}
+
+#[test]
+fn keys_do_nothing_without_tabs() {
+    let (mut state, recorder) = start(tabs(&["a"], 0));
+    state.update(Event::TabUpdate(Vec::new()));
+
+    for key in [
+        BareKey::Char('j'),
+        BareKey::Enter,
+        BareKey::Char('x'),
+        BareKey::Char('-'),
+    ] {
+        press(&mut state, key);
+    }
+    assert!(matches!(state.mode, Mode::Normal(_)));
+    assert_eq!(recorder.take(), []);
+
+    let mut frame = String::new();
+    state.render_frame(&mut frame, 10, 40).unwrap();
+    assert!(frame.contains("no tabs"));
+}
+
+#[test]
+fn selection_moves_on_when_another_client_closes_its_tab() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c", "d"], 0));
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('x'));
+    press(&mut state, BareKey::Char('y'));
+    assert_eq!(recorder.take(), [Call::CloseTab(11)]);
+    assert_eq!(selected_name(&state).as_deref(), Some("b"));
+
+    // someone else closes c, where the selection was going, before b is gone
+    state.update(Event::TabUpdate(remaining(
+        &[(10, "a"), (11, "b"), (13, "d")],
+        0,
+    )));
+    state.update(Event::TabUpdate(remaining(&[(10, "a"), (13, "d")], 0)));
+    assert_eq!(selected_name(&state).as_deref(), Some("d"));
+
+    press(&mut state, BareKey::Enter);
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(1)]);
+}
+
+#[test]
+fn selection_stays_on_its_tab_through_other_clients_changes() {
+    let (mut state, recorder) = start(tabs(&["a", "b", "c", "d"], 0));
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('j'));
+    assert_eq!(selected_name(&state).as_deref(), Some("c"));
+
+    // another client renames a tab and moves focus
+    state.update(Event::TabUpdate(remaining(
+        &[(10, "api"), (11, "b"), (12, "c"), (13, "d")],
+        3,
+    )));
+    assert_eq!(selected_name(&state).as_deref(), Some("c"));
+
+    // then closes the selected tab, with nothing pending in room
+    state.update(Event::TabUpdate(remaining(
+        &[(10, "api"), (11, "b"), (13, "d")],
+        3,
+    )));
+    assert_eq!(selected_name(&state).as_deref(), Some("d"));
+
+    press(&mut state, BareKey::Enter);
+    assert_eq!(recorder.take(), [Call::ClosePlugin, Call::FocusTab(2)]);
+}
+
+#[test]
+fn durations_round_down_to_their_largest_unit() {
+    assert_eq!(duration(0), "0s");
+    assert_eq!(duration(59), "59s");
+    assert_eq!(duration(60), "1m");
+    assert_eq!(duration(3599), "59m");
+    assert_eq!(duration(3600), "1h");
+    assert_eq!(duration(86399), "23h");
+    assert_eq!(duration(86400), "1d");
+}
+
+#[test]
+fn durations_parse_with_or_without_a_unit() {
+    assert_eq!(parse_duration("0"), Some(0));
+    assert_eq!(parse_duration(" 90 "), Some(90));
+    assert_eq!(parse_duration("59s"), Some(59));
+    assert_eq!(parse_duration("30m"), Some(1800));
+    assert_eq!(parse_duration("12h"), Some(43200));
+    assert_eq!(parse_duration("7d"), Some(604800));
+
+    for invalid in ["", "d", "5w", "5 m", "1.5h", "-5m", "9223372036854775807d"] {
+        assert_eq!(parse_duration(invalid), None, "{invalid}");
+    }
+}
+
+fn pipe(state: &mut State, name: &str) {
+    state.pipe(PipeMessage {
+        source: PipeSource::Keybind,
+        name: name.to_string(),
+        payload: None,
+        args: Default::default(),
+        is_private: true,
+    });
+}
+
+#[test]
+fn preview_dumps_the_same_pane_again_on_updates() {
+    let (mut state, recorder) = start(tabs(&["a", "b"], 0));
+    state.preview = true;
+    let panes = PaneManifest {
+        panes: [(
+            0,
+            vec![PaneInfo {
+                id: 1,
+                is_focused: true,
+                ..Default::default()
+            }],
+        )]
+        .into(),
+    };
+
+    state.update(Event::PaneUpdate(panes.clone()));
+    assert_eq!(
+        recorder.take(),
+        [
+            Call::PostToWorker("dump_pane".to_string()),
+            Call::SetTimeout
+        ]
+    );
+
+    state.update(Event::PaneUpdate(panes));
+    assert_eq!(
+        recorder.take(),
+        [Call::PostToWorker("dump_pane".to_string())]
+    );
+
+    state.update(Event::Timer(1.0));
+    assert_eq!(
+        recorder.take(),
+        [
+            Call::PostToWorker("dump_pane".to_string()),
+            Call::SetTimeout
+        ]
+    );
+}
+
+#[test]
+fn custom_action_runs_and_renames_through_the_host() {
+    let (mut state, recorder) = start(tabs(&["api", "web"], 0));
+    state.custom_actions.insert(
+        'Z',
+        Step::parse_script("focus; run cargo test; rename {name} (testing)").unwrap(),
+    );
+
+    press(&mut state, BareKey::Char('j'));
+    press(&mut state, BareKey::Char('Z'));
+    assert_eq!(
+        recorder.take(),
+        [
+            Call::FocusTab(1),
+            Call::OpenCommandPane("cargo".to_string()),
+            Call::RenameTab(11, "web (testing)".to_string()),
+            Call::ClosePlugin,
+        ]
+    );
+
+    // the rename is pending like any other, so an update that's yet to show it keeps the selection
+    state.update(Event::TabUpdate(tabs(&["api", "web"], 1)));
+    assert_eq!(selected_name(&state).as_deref(), Some("web"));
+    state.update(Event::TabUpdate(tabs(&["api", "web (testing)"], 1)));
+    assert_eq!(selected_name(&state).as_deref(), Some("web (testing)"));
+}
+
+#[test]
+fn session_detail_fits_the_pane() {
+    let (mut state, _) = start(tabs(&["a"], 0));
+    state.sessions = vec![SessionInfo {
+        name: "big".to_string(),
+        tabs: (0..30)
+            .map(|position| TabInfo {
+                position,
+                name: format!("tab {position} {}", "x".repeat(80)),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }];
+    state.mode = Mode::Sessions(Default::default());
+
+    // beside the list: nothing is drawn past the last row or the last column
+    let mut frame = String::new();
+    state.render_frame(&mut frame, 10, 80).unwrap();
+    assert!(frame.contains("\u{1b}[10;40H"));
+    assert!(!frame.contains("\u{1b}[11;40H"));
+    assert!(frame.contains("… 25 more"));
+    // the panel starts on column 40, leaving 41 columns of the 80
+    let text = width::strip_colors(&frame);
+    assert!(text
+        .split(";40H")
+        .skip(1)
+        .all(|line| width::width(line.split('\u{1b}').next().unwrap()) <= 41));
+
+    // below it: the frame stays within the pane's rows
+    frame.clear();
+    state.render_frame(&mut frame, 10, 40).unwrap();
+    let text = width::strip_colors(&frame);
+    let last_line = text.rsplit("\u{1b}[K").next().unwrap();
+    assert_eq!(last_line.lines().count(), 10);
+    assert!(frame.contains("… 27 more"));
+}
+
+#[test]
+fn toggle_keeps_a_room_it_launched_then_hides_it() {
+    let (mut state, recorder) = start(tabs(&["a", "b"], 0));
+
+    pipe(&mut state, "toggle");
+    assert_eq!(recorder.take(), [Call::ShowPlugin]);
+
+    state.rendered = true;
+    pipe(&mut state, "toggle");
+    assert_eq!(recorder.take(), [Call::HidePlugin]);
+
+    state.update(Event::Visible(false));
+    pipe(&mut state, "toggle");
+    assert_eq!(recorder.take(), [Call::ShowPlugin]);
+}
+
+#[test]
+fn data_pipes_refuse_paths_outside_the_host_folder() {
+    let (mut state, _) = start(tabs(&["a"], 0));
+
+    for name in ["export_data", "import_data"] {
+        for path in ["/etc/room.json", "../room.json", "backups/../../room.json"] {
+            state.status = None;
+            state.pipe(PipeMessage {
+                source: PipeSource::Keybind,
+                name: name.to_string(),
+                payload: Some(path.to_string()),
+                args: Default::default(),
+                is_private: true,
+            });
+
+            assert_eq!(
+                state.status,
+                Some(format!(
+                    "{path} is outside the folder Zellij was started in"
+                )),
+            );
+        }
+    }
+}
+
+#[test]
+fn resident_room_hides_instead_of_closing() {
+    let (mut state, recorder) = start(tabs(&["a", "b"], 0));
+    state.resident = true;
+
+    press(&mut state, BareKey::Enter);
+    assert_eq!(recorder.take(), [Call::HidePlugin, Call::FocusTab(0)]);
+}
+
+/// Stands in for Zellij's only host function so the tests link natively. The paths they cover
+/// reach Zellij through the recording `Host` instead, so it's never called.
+#[cfg(not(target_arch = "wasm32"))]
+#[no_mangle]
+extern "C" fn host_run_plugin_command() {}
@@ -0,0 +1,181 @@
+use crate::error::RoomError;
+use owo_colors::{AnsiColors, Style};
+use std::collections::BTreeMap;
+
+const COLORS: [(&str, AnsiColors); 16] = [
+    ("black", AnsiColors::Black),
+    ("red", AnsiColors::Red),
+    ("green", AnsiColors::Green),
+    ("yellow", AnsiColors::Yellow),
+    ("blue", AnsiColors::Blue),
+    ("magenta", AnsiColors::Magenta),
+    ("cyan", AnsiColors::Cyan),
+    ("white", AnsiColors::White),
+    ("bright_black", AnsiColors::BrightBlack),
+    ("bright_red", AnsiColors::BrightRed),
+    ("bright_green", AnsiColors::BrightGreen),
+    ("bright_yellow", AnsiColors::BrightYellow),
+    ("bright_blue", AnsiColors::BrightBlue),
+    ("bright_magenta", AnsiColors::BrightMagenta),
+    ("bright_cyan", AnsiColors::BrightCyan),
+    ("bright_white", AnsiColors::BrightWhite),
+];
+
+/// Styles from the `theme` preset and the `theme_<part>` configuration keys.
+pub struct Theme {
+    /// The tab that's focused in the session.
+    pub active_tab: Style,
+    /// The highlighted row of the list and of the pickers.
+    pub selected: Style,
+    /// Tabs without terminals: empty or plugin only.
+    pub empty_tab: Style,
+    /// Tabs nobody focused for longer than `stale_after`.
+    pub stale_tab: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            active_tab: Style::new().red().bold(),
+            selected: Style::new().on_cyan(),
+            empty_tab: Style::new().dimmed(),
+            stale_tab: Style::new().yellow(),
+        }
+    }
+}
+
+impl Theme {
+    /// The named themes `theme` picks from: `default`, `high-contrast`, and `deuteranopia`, which
+    /// keeps apart what the default tells apart by red and cyan only.
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name {
+            "default" => Some(Theme::default()),
+            "high-contrast" => Some(Theme {
+                active_tab: Style::new().bright_white().bold().underline(),
+                selected: Style::new().black().on_bright_white(),
+                empty_tab: Style::new().italic(),
+                stale_tab: Style::new().bright_yellow().bold(),
+            }),
+            "deuteranopia" => Some(Theme {
+                active_tab: Style::new().bright_yellow().bold(),
+                selected: Style::new().bright_white().on_blue(),
+                empty_tab: Style::new().dimmed(),
+                stale_tab: Style::new().bright_cyan().italic(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Overrides the parts of the theme set with `theme_<part>` keys. Values with a word
+    /// `parse_style` doesn't know are passed to `report`, and that part keeps the preset's style.
+    pub fn with_configuration(
+        mut self,
+        configuration: &BTreeMap<String, String>,
+        mut report: impl FnMut(RoomError),
+    ) -> Theme {
+        for (key, style) in [
+            ("theme_active_tab", &mut self.active_tab),
+            ("theme_selected", &mut self.selected),
+            ("theme_empty_tab", &mut self.empty_tab),
+            ("theme_stale_tab", &mut self.stale_tab),
+        ] {
+            if let Some(value) = configuration.get(key) {
+                match parse_style(value) {
+                    Some(parsed) => *style = parsed,
+                    None => report(RoomError::Config {
+                        key: key.to_string(),
+                        value: value.clone(),
+                    }),
+                }
+            }
+        }
+
+        self
+    }
+}
+
+/// Parses a space separated list of effects (`bold`, `dimmed`, `italic`, `underline`,
+/// `strikethrough`) and colors (`red`, `bright_black`, `on_blue`...), or `none` for a plain style.
+/// `None` when a word is none of those.
+fn parse_style(value: &str) -> Option<Style> {
+    let color = |name: &str| {
+        COLORS
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, color)| *color)
+    };
+
+    value
+        .split_whitespace()
+        .try_fold(Style::new(), |style, word| match word {
+            "none" => Some(style),
+            "bold" => Some(style.bold()),
+            "dimmed" => Some(style.dimmed()),
+            "italic" => Some(style.italic()),
+            "underline" => Some(style.underline()),
+            "strikethrough" => Some(style.strikethrough()),
+            word => match word.strip_prefix("on_").and_then(color) {
+                Some(background) => Some(style.on_color(background)),
+                None => color(word).map(|foreground| style.color(foreground)),
+            },
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_style, Theme};
+    use owo_colors::Style;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn colors_and_effects_combine() {
+        assert_eq!(
+            parse_style("bold bright_black on_blue"),
+            Some(Style::new().bold().bright_black().on_blue())
+        );
+        assert_eq!(
+            parse_style(" italic  underline strikethrough dimmed red "),
+            Some(
+                Style::new()
+                    .italic()
+                    .underline()
+                    .strikethrough()
+                    .dimmed()
+                    .red()
+            )
+        );
+    }
+
+    #[test]
+    fn none_is_a_plain_style() {
+        assert_eq!(parse_style("none"), Some(Style::new()));
+        assert_eq!(parse_style(""), Some(Style::new()));
+    }
+
+    #[test]
+    fn unknown_words_are_rejected() {
+        assert_eq!(parse_style("bold purple"), None);
+        assert_eq!(parse_style("on_bold"), None);
+        assert_eq!(parse_style("Red"), None);
+    }
+
+    #[test]
+    fn unknown_words_are_reported_and_keep_the_preset() {
+        let configuration = BTreeMap::from([
+            ("theme_selected".to_string(), "on_purple".to_string()),
+            ("theme_active_tab".to_string(), "green".to_string()),
+        ]);
+        let mut errors = Vec::new();
+
+        let theme = Theme::default().with_configuration(&configuration, |error| {
+            errors.push(error.to_string());
+        });
+
+        assert_eq!(theme.selected, Theme::default().selected);
+        assert_eq!(theme.active_tab, Style::new().green());
+        assert_eq!(
+            errors,
+            ["invalid theme_selected \"on_purple\", using the default"]
+        );
+    }
+}
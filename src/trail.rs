@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+pub const FILE: &str = "trail.json";
+
+/// How many tab switches a trail remembers.
+const LIMIT: usize = 50;
+
+/// The tabs switched to from room in one session, in order, to go back and forward through like a
+/// browser's history. Tabs are kept by name, like the recent list, so the trail outlives room.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Trail {
+    names: Vec<String>,
+    /// Where in `names` back and forward start from.
+    index: usize,
+}
+
+impl Trail {
+    /// Records a switch from one tab to another. Switching after going back forgets the tabs that
+    /// were ahead, as a browser does.
+    pub fn visit(&mut self, from: &str, to: &str) {
+        if self.current() != Some(from) {
+            self.push(from);
+        }
+
+        self.push(to);
+    }
+
+    fn push(&mut self, name: &str) {
+        self.names.truncate(self.index + 1);
+
+        if self.current() != Some(name) {
+            self.names.push(name.to_string());
+        }
+
+        if self.names.len() > LIMIT {
+            self.names.remove(0);
+        }
+
+        self.index = self.names.len() - 1;
+    }
+
+    fn current(&self) -> Option<&str> {
+        self.names.get(self.index).map(String::as_str)
+    }
+
+    /// Steps back to the closest earlier tab that `can_visit`, returning its name.
+    pub fn back(&mut self, can_visit: impl Fn(&str) -> bool) -> Option<&str> {
+        let index = (0..self.index.min(self.names.len()))
+            .rev()
+            .find(|index| can_visit(&self.names[*index]))?;
+
+        self.index = index;
+        self.current()
+    }
+
+    /// Steps forward to the closest later tab that `can_visit`, returning its name.
+    pub fn forward(&mut self, can_visit: impl Fn(&str) -> bool) -> Option<&str> {
+        let index =
+            (self.index + 1..self.names.len()).find(|index| can_visit(&self.names[*index]))?;
+
+        self.index = index;
+        self.current()
+    }
+
+    /// The last `count` tabs up to the current one, oldest first.
+    pub fn breadcrumb(&self, count: usize) -> &[String] {
+        let end = (self.index + 1).min(self.names.len());
+
+        &self.names[end.saturating_sub(count)..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Trail, LIMIT};
+
+    fn trail(names: &[&str]) -> Trail {
+        let mut trail = Trail::default();
+        for pair in names.windows(2) {
+            trail.visit(pair[0], pair[1]);
+        }
+
+        trail
+    }
+
+    #[test]
+    fn back_and_forward_skip_tabs_that_cant_be_visited() {
+        let mut trail = trail(&["a", "b", "c", "d"]);
+        let open = |name: &str| name != "b" && name != "c";
+
+        assert_eq!(trail.back(open), Some("a"));
+        assert_eq!(trail.back(open), None);
+        assert_eq!(trail.forward(open), Some("d"));
+        assert_eq!(trail.forward(open), None);
+    }
+
+    #[test]
+    fn visiting_after_going_back_forgets_what_was_ahead() {
+        let mut trail = trail(&["a", "b", "c"]);
+
+        assert_eq!(trail.back(|_| true), Some("b"));
+        trail.visit("b", "x");
+
+        assert_eq!(trail.forward(|_| true), None);
+        assert_eq!(trail.breadcrumb(10), ["a", "b", "x"]);
+    }
+
+    #[test]
+    fn only_the_latest_tabs_are_kept() {
+        let names: Vec<String> = (0..=LIMIT + 10).map(|index| format!("t{index}")).collect();
+        let mut trail = trail(&names.iter().map(String::as_str).collect::<Vec<&str>>());
+
+        let kept = trail.breadcrumb(usize::MAX);
+        assert_eq!(kept.len(), LIMIT);
+        assert_eq!(kept.first().map(String::as_str), Some("t11"));
+        assert_eq!(kept.last().map(String::as_str), Some("t60"));
+        assert_eq!(trail.back(|_| true), Some("t59"));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{plural, storage, tags, Pending, State};
+use std::collections::BTreeSet;
+
+/// How many changes `u` can go back.
+const DEPTH: usize = 100;
+
+/// A change room made that `u` can revert. Each one holds what to restore, and applying it gives
+/// back the change that reverts it again, for `Ctrl + r`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// Tab ids with the names to give them.
+    Rename(Vec<(usize, String)>),
+    /// Tab names with the tags to give them (none removes their tags).
+    Tags(Vec<(String, Vec<String>)>),
+    /// The tabs to mark, replacing the marks.
+    Marks(BTreeSet<usize>),
+}
+
+impl Change {
+    fn describe(&self) -> String {
+        match self {
+            Change::Rename(renames) => format!("rename of {}", plural(renames.len(), "tab")),
+            Change::Tags(tags) => format!("tags of {}", plural(tags.len(), "tab")),
+            Change::Marks(_) => "marks".to_string(),
+        }
+    }
+}
+
+/// The changes that `u` reverts and the reverted ones `Ctrl + r` makes again.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    /// Remembers how to revert a change that was just made. Making a new change forgets what
+    /// could be redone.
+    pub fn record(&mut self, change: Change) {
+        self.redo.clear();
+        self.push_undo(change);
+    }
+
+    fn push_undo(&mut self, change: Change) {
+        if self.undo.len() == DEPTH {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(change);
+    }
+}
+
+impl State {
+    pub(crate) fn undo(&mut self) {
+        let Some(change) = self.history.undo.pop() else {
+            self.status = Some("nothing to undo".to_string());
+            return;
+        };
+
+        self.status = Some(format!("undid {}", change.describe()));
+
+        let redo = self.apply_change(change);
+        self.history.redo.push(redo);
+    }
+
+    pub(crate) fn redo(&mut self) {
+        let Some(change) = self.history.redo.pop() else {
+            self.status = Some("nothing to redo".to_string());
+            return;
+        };
+
+        self.status = Some(format!("redid {}", change.describe()));
+
+        let undo = self.apply_change(change);
+        self.history.push_undo(undo);
+    }
+
+    /// Makes the change, returning the one that reverts it.
+    fn apply_change(&mut self, change: Change) -> Change {
+        self.log(format_args!("action apply {change:?}"));
+
+        match change {
+            Change::Rename(renames) => {
+                let mut reverted = Vec::new();
+                let mut moved = Vec::new();
+
+                for (tab_id, name) in &renames {
+                    if let Some(tab) = self.tabs.iter().find(|tab| tab.tab_id == *tab_id) {
+                        reverted.push((*tab_id, tab.name.clone()));
+                        moved.push((tab.name.clone(), name.clone()));
+                    }
+
+                    self.host.rename_tab(*tab_id, name);
+                }
+
+                self.rename_notes(moved.iter().map(|(from, to)| (from.as_str(), to.as_str())));
+
+                self.pending_selection = self.selected_tab().map(|tab| tab.tab_id);
+                self.pending.push(Pending::Rename(renames));
+
+                Change::Rename(reverted)
+            }
+            Change::Tags(tags) => {
+                self.invalidate_rows();
+
+                let reverted = tags
+                    .iter()
+                    .map(|(name, _)| {
+                        let current = self.tags.get(name).cloned().unwrap_or_default();
+
+                        (name.clone(), current)
+                    })
+                    .collect();
+
+                for (name, tags) in tags {
+                    if tags.is_empty() {
+                        self.tags.remove(&name);
+                    } else {
+                        self.tags.insert(name, tags);
+                    }
+                }
+
+                storage::save(tags::FILE, &self.tags);
+
+                Change::Tags(reverted)
+            }
+            Change::Marks(marked) => Change::Marks(std::mem::replace(&mut self.marked, marked)),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Splits styled text into escape sequences (which take no room on screen) and the visible
+/// characters between them.
+fn segments(text: &str) -> impl Iterator<Item = (&str, bool)> {
+    let mut rest = text;
+
+    std::iter::from_fn(move || {
+        let c = rest.chars().next()?;
+
+        let len = if c == '\u{1b}' {
+            escape_len(rest)
+        } else {
+            c.len_utf8()
+        };
+
+        let (segment, tail) = rest.split_at(len);
+        rest = tail;
+
+        Some((segment, c != '\u{1b}'))
+    })
+}
+
+/// Length of the escape sequence `text` starts with: CSI sequences run up to their final byte,
+/// anything else is taken as a two byte escape.
+fn escape_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+
+    if bytes.get(1) != Some(&b'[') {
+        return text.chars().take(2).map(char::len_utf8).sum();
+    }
+
+    bytes
+        .iter()
+        .skip(2)
+        .position(|byte| (0x40..=0x7e).contains(byte))
+        .map_or(bytes.len(), |final_byte| final_byte + 3)
+}
+
+/// Columns `text` takes on screen, ignoring color codes and counting wide characters twice.
+pub fn width(text: &str) -> usize {
+    segments(text)
+        .filter(|(_, visible)| *visible)
+        .filter_map(|(segment, _)| segment.chars().next()?.width())
+        .sum()
+}
+
+/// Cuts `text` down to `columns` columns. Escape sequences are all kept, so styles opened before
+/// the cut are still reset after it.
+pub fn truncate(text: &str, columns: usize) -> String {
+    if width(text) <= columns {
+        return text.to_string();
+    }
+
+    let mut used = 0;
+
+    segments(text)
+        .filter(|(segment, visible)| {
+            if !visible {
+                return true;
+            }
+
+            used += segment.chars().next().and_then(|c| c.width()).unwrap_or(0);
+            used <= columns
+        })
+        .map(|(segment, _)| segment)
+        .collect()
+}
+
+/// Drops the color codes from `text`, keeping the cursor movements.
+pub fn strip_colors(text: &str) -> String {
+    segments(text)
+        .filter(|(segment, visible)| *visible || !segment.ends_with('m'))
+        .map(|(segment, _)| segment)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{strip_colors, truncate, width};
+
+    #[test]
+    fn escape_sequences_take_no_columns() {
+        assert_eq!(width("\u{1b}[1;31mlogs\u{1b}[0m"), 4);
+        assert_eq!(width("\u{1b}7a\u{1b}8"), 1);
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        assert_eq!(width("日本"), 4);
+        assert_eq!(truncate("日本語", 3), "日");
+    }
+
+    #[test]
+    fn truncation_keeps_text_that_fits_exactly() {
+        assert_eq!(truncate("logs", 4), "logs");
+        assert_eq!(truncate("logs", 3), "log");
+        assert_eq!(
+            truncate("\u{1b}[31mlogs\u{1b}[0m", 2),
+            "\u{1b}[31mlo\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn stripping_colors_keeps_cursor_movements() {
+        assert_eq!(
+            strip_colors("\u{1b}[2;1H\u{1b}[31mlogs\u{1b}[0m"),
+            "\u{1b}[2;1Hlogs"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const FILE: &str = "workspaces.json";
+
+/// The workspaces defined in one session: named sets of tab names, plus the one currently
+/// narrowing the tab list.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Workspaces {
+    pub active: Option<String>,
+    pub members: BTreeMap<String, Vec<String>>,
+}
+
+impl Workspaces {
+    /// Adds the tab to the workspace, or removes it when it's already a member. Workspaces left
+    /// without members are forgotten.
+    pub fn toggle(&mut self, workspace: &str, tab_name: &str) {
+        let members = self.members.entry(workspace.to_string()).or_default();
+
+        match members.iter().position(|member| member == tab_name) {
+            Some(index) => {
+                members.remove(index);
+            }
+            None => members.push(tab_name.to_string()),
+        }
+
+        if members.is_empty() {
+            self.remove(workspace);
+        }
+    }
+
+    pub fn remove(&mut self, workspace: &str) {
+        self.members.remove(workspace);
+
+        if self.active.as_deref() == Some(workspace) {
+            self.active = None;
+        }
+    }
+
+    pub fn hides(&self, tab_name: &str) -> bool {
+        self.active
+            .as_ref()
+            .and_then(|active| self.members.get(active))
+            .is_some_and(|members| !members.iter().any(|member| member == tab_name))
+    }
+}